@@ -4,21 +4,55 @@ use log::{debug, info};
 use lp_modeler::{
     constraint,
     dsl::*,
-    solvers::{self, SolverTrait},
+    solvers::{self, Solution, SolverTrait},
 };
 use maplit::{btreemap, btreeset};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
+mod options;
+mod report;
 mod types;
+mod warm;
+use crate::options::{Options, TieBreak, EPSILON};
+use crate::report::RunSummary;
 use crate::types::*;
 
+// Preference multiplier used to force a skill to be trained first.
+const TRADEOFF_PRIORITY_MULTIPLIER: f32 = 1000.0;
+// Objective weight of the worst-off skill's progress in --maxmin mode.
+// It must dwarf the ordinary objective, which is then only a tie-breaker.
+const MAXMIN_WEIGHT: f32 = 1000.0;
+// Exit codes for runs that left targets unmet: stopped by --min-daily-roi,
+// or given up on as unreachable, e.g. by running out of lifetime budget.
+const EXIT_STOPPED_EARLY: i32 = 2;
+const EXIT_UNMET: i32 = 3;
+// The share of the usual cost paid to regain ranks held before.
+const RELEARN_DISCOUNT: f32 = 0.5;
+// Objective cost per hour of ROI spilled with --spill. Small enough that
+// spilling into any skill worth training still pays off, but enough to break ties in favour
+// of keeping ROI where it was earned.
+const SPILL_COST: f32 = 1e-3;
+// The largest relative change --perturb-test makes to any preference, and
+// the relative change in ROI it then warns about. Any plan that's optimal
+// for the perturbed objective is within about twice the former of the best
+// ROI, so the latter only trips on a numerically fragile model.
+const PERTURBATION: f32 = 1e-3;
+const PERTURB_TOLERANCE: f32 = 1e-2;
+// Objective weight of an hour of skill upkeep. It must dwarf any training,
+// so that upkeep gets its time first.
+const MAINTENANCE_WEIGHT: f32 = 1000.0;
+// How many seeds --jitter runs, to see how far completion dates spread.
+const JITTER_RUNS: u64 = 20;
+// The pseudo-segment that time banked on sabbatical is spent from.
+const BANKED_SEGMENT: &str = "Banked time";
+
 lazy_static! {
-    static ref ATTRIBUTES: BTreeSet<Skill> = btreeset! {
+    static ref ATTRIBUTES: BTreeSet<&'static str> = btreeset! {
         "Strength", "Dexterity", "Stamina",
         "Charisma", "Manipulation", "Appearance",
         "Perception", "Intelligence", "Wits",
     };
-    static ref ABILITIES: BTreeSet<Skill> = btreeset! {
+    static ref ABILITIES: BTreeSet<&'static str> = btreeset! {
         "Archery", "Athletics", "Awareness",
         "Brawl", "Bureaucracy", "Craft",
         "Dodge", "Integrity", "Investigation",
@@ -30,195 +64,2486 @@ lazy_static! {
         "Thrown", "War",
         "Firearms", "Driving",
     };
-    static ref PSIONICS: BTreeSet<Skill> = btreeset! {
+    static ref PSIONICS: BTreeSet<&'static str> = btreeset! {
         "Dreamwalking", "Illusion",
     };
 }
 
-fn main() {
-    env_logger::init();
+fn main() -> anyhow::Result<()> {
+    // The logger lets everything of ours through, and the max level does the
+    // filtering instead, so that Task::Log can raise it as well as lower it.
+    let level = env_logger::Builder::from_default_env().build().filter();
+    env_logger::Builder::from_default_env()
+        .filter_module("shards", log::LevelFilter::Trace)
+        .init();
+    log::set_max_level(level);
+    let options = Options::from_args(std::env::args().skip(1))?;
+    if options.list_skills {
+        for (category, skills) in [
+            ("Attributes", &*ATTRIBUTES),
+            ("Abilities", &*ABILITIES),
+            ("Psionics", &*PSIONICS),
+        ] {
+            println!("{}:", category);
+            for skill in skills.iter() {
+                println!("  {}", skill);
+            }
+        }
+        return Ok(());
+    }
 
     let start = NaiveDate::from_ymd_opt(2009, 10, 17).unwrap();
     println!("{}: Chapter 2.1", start);
     let schedule: Vec<Task> = vec![
         Task::Baseline {
-            name: "Amu",
+            name: "Amu".into(),
+            template: None,
             skills: btreemap! {
-                "Dreamwalking" => 1.0,
-                "Illusion" => 1.0,
-                "Integrity" => 2.0,
-                "Lore" => 1.0,
+                "Dreamwalking".into() => 1.0,
+                "Illusion".into() => 1.0,
+                "Integrity".into() => 2.0,
+                "Lore".into() => 1.0,
             },
         },
         Task::Schedule {
-            name: "Amu",
+            name: "Amu".into(),
             segment: btreemap! {
-                "School" => 1.0,
-                "Afternoon" => 2.0,
-                "Evening" => 1.0,
-                "Sleep" => 0.5,
+                "School".into() => 1.0,
+                "Afternoon".into() => 2.0,
+                "Evening".into() => 1.0,
+                "Sleep".into() => 0.5,
             },
         },
         Task::SafetyLimit {
-            name: "Amu",
+            name: "Amu".into(),
             limit: btreemap! {
-                "Integrity" => 2.0,
+                "Integrity".into() => 2.0,
             },
         },
         Task::ScheduleLimit {
-            name: "Amu",
+            name: "Amu".into(),
             limit: btreemap! {
-                "School" => vec!["Illusion", "Lore"],
-                "Sleep" => vec!["Dreamwalking", "Integrity"],
+                "School".into() => vec!["Illusion".into(), "Lore".into()],
+                "Sleep".into() => vec!["Dreamwalking".into(), "Integrity".into()],
             },
+            focus: btreemap! {},
         },
         Task::Overlap {
-            name: "Amu",
+            name: "Amu".into(),
             when: vec![
                 Overlap {
-                    combo: vec!["Illusion", "Dreamwalking"],
+                    combo: vec!["Illusion".into(), "Dreamwalking".into()],
                     bonus: 1.25,
+                    segment_bonus: btreemap! {},
+                    min_rank: btreemap! {},
                 },
                 Overlap {
-                    combo: vec!["Dreamwalking", "Integrity"],
+                    combo: vec!["Dreamwalking".into(), "Integrity".into()],
                     bonus: 1.25,
+                    segment_bonus: btreemap! {},
+                    min_rank: btreemap! {},
                 },
                 Overlap {
-                    combo: vec!["Lore", "Integrity"],
+                    combo: vec!["Lore".into(), "Integrity".into()],
                     bonus: 1.1,
+                    segment_bonus: btreemap! {},
+                    min_rank: btreemap! {},
                 },
             ],
         },
         Task::Target {
-            name: "Amu",
+            name: "Amu".into(),
             target: btreemap! {
-                "Dreamwalking" => 2.0,
-                "Illusion" => 2.0,
-                "Integrity" => 3.0,
-                "Lore" => 1.5,
+                "Dreamwalking".into() => 2.0,
+                "Illusion".into() => 2.0,
+                "Integrity".into() => 3.0,
+                "Lore".into() => 1.5,
             },
         },
     ];
 
-    // Run the schedule.
+    let summary = run(start, schedule.clone(), &options);
+    if options.validate_only {
+        for problem in summary.problems.iter() {
+            println!("Problem: {}", problem);
+        }
+        if !summary.problems.is_empty() {
+            std::process::exit(1);
+        }
+        println!("Schedule is valid.");
+        return Ok(());
+    }
+    summary.print(&options);
+    if !summary.metrics.is_empty() {
+        match options.metrics.as_ref() {
+            Some(path) => {
+                let mut file = std::fs::File::create(path)?;
+                summary.write_metrics(&mut file)?;
+            }
+            None => summary.write_metrics(&mut std::io::stdout())?,
+        }
+    }
+    if options.weekly_plan {
+        summary.print_weekly_plan(options.precision);
+    }
+    if options.leaderboard {
+        summary.print_leaderboard(start);
+    }
+    if let Some(path) = options.report.as_ref() {
+        let mut file = std::fs::File::create(path)?;
+        summary.write_report(&mut file, options.precision)?;
+    }
+    if let Some(path) = options.skill_log.as_ref() {
+        let mut file = std::fs::File::create(path)?;
+        summary.write_skill_log(&mut file)?;
+    }
+    if options.balanced {
+        // Rerun in the default mode to show what balancing bought us.
+        let default_options = Options {
+            balanced: false,
+            quiet: true,
+            capacity: false,
+            try_overlap: None,
+            perturb_test: None,
+            ..options.clone()
+        };
+        let default_summary = run(start, schedule.clone(), &default_options);
+        if let Some(spread) = default_summary.completion_spread() {
+            println!("Completion spread in default mode: {} days", spread);
+        }
+    }
+    if options.pareto {
+        // Sweep the cap on concurrent targets, from one up to as many as
+        // anyone trained, and see what each costs in days and wasted time.
+        let most = summary
+            .training
+            .keys()
+            .fold(
+                BTreeMap::new(),
+                |mut counts: BTreeMap<Name, usize>, (name, _)| {
+                    *counts.entry(name.clone()).or_default() += 1;
+                    counts
+                },
+            )
+            .into_values()
+            .max()
+            .unwrap_or(0);
+        let mut points = vec![];
+        for n in 1..=most {
+            let sweep_options = Options {
+                quiet: true,
+                pareto: false,
+                capacity: false,
+                try_overlap: None,
+                perturb_test: None,
+                max_concurrent: Some(n),
+                ..options.clone()
+            };
+            let sweep = run(start, schedule.clone(), &sweep_options);
+            points.push((n, sweep.days, sweep.wasted_time));
+        }
+        report::write_pareto(&points, &mut std::io::stdout())?;
+    }
+    if options.overlap_value {
+        // Rerun without each multi-skill overlap in turn, to see what it's
+        // worth. The runs are independent, so do them all at once.
+        let removals: Vec<(usize, usize, Name, Vec<Skill>)> = schedule
+            .iter()
+            .enumerate()
+            .flat_map(|(t, task)| match task {
+                Task::Overlap { name, when } => when
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, overlap)| overlap.combo.len() > 1)
+                    .map(|(o, overlap)| (t, o, name.clone(), overlap.combo.clone()))
+                    .collect(),
+                _ => vec![],
+            })
+            .collect();
+        let without_options = Options {
+            quiet: true,
+            overlap_value: false,
+            capacity: false,
+            try_overlap: None,
+            perturb_test: None,
+            ..options.clone()
+        };
+        let values: Vec<(Name, Vec<Skill>, usize, f32)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = removals
+                .iter()
+                .map(|(t, o, name, combo)| {
+                    let mut schedule = schedule.clone();
+                    if let Task::Overlap { when, .. } = &mut schedule[*t] {
+                        when.remove(*o);
+                    }
+                    let options = &without_options;
+                    scope.spawn(move || {
+                        let without = run(start, schedule, options);
+                        (name.clone(), combo.clone(), without.days, without.roi)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Overlap value run failed"))
+                .collect()
+        });
+        summary.print_overlap_values(&values, options.precision);
+    }
+    if let Some((name, deadline)) = options.solve_schedule.as_ref() {
+        // Binary search for the smallest scale of their schedule that still
+        // gets all their targets done in time.
+        let Some(segments) = schedule.iter().rev().find_map(|task| match task {
+            Task::Schedule { name: n, segment } if n == name => Some(segment),
+            _ => None,
+        }) else {
+            anyhow::bail!("{} has no schedule to scale", name);
+        };
+        let search_options = Options {
+            quiet: true,
+            capacity: false,
+            try_overlap: None,
+            perturb_test: None,
+            solve_schedule: None,
+            ..options.clone()
+        };
+        let meets_deadline = |scale: f32| {
+            let schedule = scale_schedule(&schedule, name, scale);
+            run(start, schedule, &search_options).finished_by(name, *deadline)
+        };
+        let mut high = 1.0;
+        while !meets_deadline(high) {
+            high *= 2.0;
+            if high > 1024.0 {
+                anyhow::bail!("{} can't finish by {} on any schedule", name, deadline);
+            }
+        }
+        let mut low = 0.0;
+        while high - low > 0.001 {
+            let middle = (low + high) / 2.0;
+            if meets_deadline(middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        println!(
+            "Smallest schedule for {} to finish by {}: {:.*}x the current one",
+            name, deadline, options.precision, high
+        );
+        for (seg, duration) in segments.iter() {
+            println!("  {}: {:.*} hours", seg, options.precision, duration * high);
+        }
+    }
+    if let Some((name, deadline)) = options.reverse_plan.as_ref() {
+        // Binary search for the lowest shift of their starting ranks that
+        // still gets all their targets done in time.
+        let Some(ranks) = baseline_ranks(&schedule, name) else {
+            anyhow::bail!("{} has no baseline to shift", name);
+        };
+        let search_options = Options {
+            quiet: true,
+            capacity: false,
+            try_overlap: None,
+            perturb_test: None,
+            reverse_plan: None,
+            ..options.clone()
+        };
+        let meets_deadline = |shift: f32| {
+            let schedule = shift_baseline(&schedule, name, shift);
+            run(start, schedule, &search_options).finished_by(name, *deadline)
+        };
+        let mut high = 0.0;
+        while !meets_deadline(high) {
+            high = (high * 2.0_f32).max(1.0);
+            if high > 64.0 {
+                anyhow::bail!("{} can't finish by {} from any start", name, deadline);
+            }
+        }
+        // Ranks can't go below zero, so there's no point shifting further.
+        let mut low = -ranks.values().cloned().fold(0.0, f32::max);
+        if meets_deadline(low) {
+            high = low;
+        }
+        while high - low > 0.01 {
+            let middle = (low + high) / 2.0;
+            if meets_deadline(middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        println!(
+            "Lowest start for {} to finish by {}: {:+.*} ranks on the current one",
+            name, deadline, options.precision, high
+        );
+        for (skill, rank) in ranks.iter() {
+            println!(
+                "  {}: {:.*}",
+                skill,
+                options.precision,
+                (rank + high).max(0.0)
+            );
+        }
+    }
+    if options.jitter.is_some() {
+        // Rerun with other seeds, and see how far each completion moves.
+        let mut dates: BTreeMap<(Name, Skill), Vec<NaiveDate>> = BTreeMap::new();
+        for seed in options.seed..options.seed + JITTER_RUNS {
+            let jitter_options = Options {
+                quiet: true,
+                capacity: false,
+                try_overlap: None,
+                perturb_test: None,
+                seed,
+                ..options.clone()
+            };
+            let jittered = run(start, schedule.clone(), &jitter_options);
+            for (date, name, skill) in jittered.completions {
+                dates.entry((name, skill)).or_default().push(date);
+            }
+        }
+        report::print_jitter_spread(&dates, JITTER_RUNS);
+    }
+    if let Some(path) = options.golden.as_ref() {
+        if !std::path::Path::new(path).exists() {
+            std::fs::write(path, summary.golden.join("\n") + "\n")?;
+            println!("Wrote golden file {}", path);
+        } else {
+            let expected = std::fs::read_to_string(path)?;
+            match report::first_divergence(expected.lines(), &summary.golden) {
+                Some((expected, actual)) => {
+                    println!("Diverged from golden file {}:", path);
+                    println!("  expected: {}", expected);
+                    println!("  actual:   {}", actual);
+                    std::process::exit(1);
+                }
+                None => println!("Matches golden file {}", path),
+            }
+        }
+    }
+    info!("Simulation complete.");
+    // Let scripts tell whether the plan worked. Errors exit with 1. Stopping
+    // early is only a failure if it left some target unmet.
+    if summary.stopped_early && !summary.unmet.is_empty() {
+        std::process::exit(EXIT_STOPPED_EARLY);
+    }
+    if !summary.unmet.is_empty() {
+        std::process::exit(EXIT_UNMET);
+    }
+    Ok(())
+}
+
+// The same schedule, with every Schedule, Calendar and CramSchedule task for
+// one person scaled.
+fn scale_schedule(schedule: &[Task], name: &str, scale: f32) -> Vec<Task> {
+    schedule
+        .iter()
+        .cloned()
+        .map(|task| match task {
+            Task::Schedule { name: n, segment } if n == name => Task::Schedule {
+                name: n,
+                segment: segment
+                    .into_iter()
+                    .map(|(seg, d)| (seg, d * scale))
+                    .collect(),
+            },
+            Task::Calendar { name: n, rules } if n == name => Task::Calendar {
+                name: n,
+                rules: rules
+                    .into_iter()
+                    .map(|rule| RecurrenceRule {
+                        hours: rule.hours * scale,
+                        ..rule
+                    })
+                    .collect(),
+            },
+            Task::CramSchedule {
+                name: n,
+                segment,
+                base,
+                deadline,
+                max,
+            } if n == name => Task::CramSchedule {
+                name: n,
+                segment,
+                base: base * scale,
+                deadline,
+                max: max * scale,
+            },
+            task => task,
+        })
+        .collect()
+}
+
+// A person's starting ranks, from their Baseline and any template it uses.
+fn baseline_ranks(schedule: &[Task], name: &str) -> Option<BTreeMap<Skill, f32>> {
+    let (template, mut skills) = schedule.iter().find_map(|task| match task {
+        Task::Baseline {
+            name: n,
+            template,
+            skills,
+        } if *n == name => Some((template.clone(), skills.clone())),
+        _ => None,
+    })?;
+    if let Some(template) = template {
+        for task in schedule {
+            if let Task::Template {
+                name,
+                skills: shared,
+            } = task
+            {
+                if *name == template {
+                    for (skill, rank) in shared {
+                        skills.entry(skill.clone()).or_insert(*rank);
+                    }
+                }
+            }
+        }
+    }
+    Some(skills)
+}
+
+// The same schedule, with one person's starting ranks all shifted by the
+// same amount, but never below zero.
+fn shift_baseline(schedule: &[Task], name: &str, shift: f32) -> Vec<Task> {
+    schedule
+        .iter()
+        .cloned()
+        .map(|task| match task {
+            Task::Baseline { name: n, .. } if n == name => Task::Baseline {
+                name: n,
+                template: None,
+                skills: baseline_ranks(schedule, name)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(skill, rank)| (skill, (rank + shift).max(0.0)))
+                    .collect(),
+            },
+            task => task,
+        })
+        .collect()
+}
+
+// Everything a Rollback restores.
+type SavedState = (
+    BTreeMap<Name, Person>,
+    NaiveDate,
+    RunSummary,
+    Vec<GroupTarget>,
+    BTreeMap<Name, Name>,
+);
+
+// Runs a schedule from the given start date until no-one has any targets left.
+fn run(start: NaiveDate, schedule: Vec<Task>, options: &Options) -> RunSummary {
     log::debug!("Schedule: {:?}", schedule);
+    let mut summary = RunSummary::default();
     let mut now = start;
-    let mut persons: BTreeMap<&str, Person> = btreemap! {};
-    for task in schedule {
+    let mut persons: BTreeMap<Name, Person> = btreemap! {};
+    let mut queue: VecDeque<Task> = schedule.into();
+    // Starting skills shared by several people, by template name.
+    let mut templates: BTreeMap<Name, BTreeMap<Skill, f32>> = btreemap! {};
+    // Saved states to return to, most recent last.
+    let mut checkpoints: Vec<SavedState> = vec![];
+    // Noise for starting ranks, with --jitter.
+    let mut jitter = options.jitter.map(|sigma| (sigma, Rng(options.seed)));
+    // Whose schedule and overlaps each follower copies, from Task::Mirror.
+    let mut leaders: BTreeMap<Name, Name> = btreemap! {};
+    // Shared targets not yet met, from Task::GroupTarget.
+    let mut groups: Vec<GroupTarget> = vec![];
+    while let Some(task) = queue.pop_front() {
+        if let Task::Schedule { name, .. } | Task::Overlap { name, .. } = &task {
+            for (follower, _) in leaders.iter().filter(|(_, leader)| *leader == name) {
+                queue.push_front(task.clone().with_name(follower.clone()));
+            }
+        }
         match task {
             Task::At { date } => {
                 if date <= now {
                     panic!("Cannot go back in time: {} < {}", date, now);
                 }
+                if options.validate_only {
+                    now = date;
+                }
                 while now < date {
-                    simulate_day(&mut persons, now);
+                    let mut results = simulate_day(&mut persons, now, options);
+                    complete_group_targets(&mut groups, &mut persons, now, &mut results, options);
+                    if !options.quiet {
+                        report::print_day(&results, options);
+                    }
+                    summary.record_completions(&results);
+                    summary.observe(&persons, &results);
                     now = now.succ_opt().unwrap();
                 }
             }
-            Task::Baseline { name, skills } => {
-                if persons.contains_key(name) {
+            Task::Checkpoint => {
+                checkpoints.push((
+                    persons.clone(),
+                    now,
+                    summary.clone(),
+                    groups.clone(),
+                    leaders.clone(),
+                ));
+            }
+            Task::Rollback => {
+                let Some(checkpoint) = checkpoints.pop() else {
+                    panic!("Rollback without a checkpoint at {}", now);
+                };
+                (persons, now, summary, groups, leaders) = checkpoint;
+                println!("{}: Rolled back to checkpoint", now);
+            }
+            // Advancing no days leaves nothing to simulate, where At would
+            // object to staying put.
+            Task::Advance { days: 0 } => {}
+            Task::Advance { days } => {
+                let date = now + chrono::Duration::days(days.into());
+                queue.push_front(Task::At { date });
+            }
+            Task::Batch { names, tasks } => {
+                // Expand in place, so the batch runs before anything after it.
+                let mut expanded = vec![];
+                for task in tasks {
+                    for name in names.iter() {
+                        expanded.push(task.clone().with_name(name.clone()));
+                    }
+                }
+                for task in expanded.into_iter().rev() {
+                    queue.push_front(task);
+                }
+            }
+            Task::GenerateCohort {
+                prefix,
+                count,
+                template,
+                skill_ranges,
+                seed,
+                tasks,
+            } => {
+                // A baseline for each member, then the shared tasks as a batch.
+                let mut rng = Rng(seed);
+                let mut expanded = vec![];
+                let mut names = vec![];
+                for i in 1..=count {
+                    let name: Name = format!("{} {}", prefix, i);
+                    let skills = skill_ranges
+                        .iter()
+                        .map(|(skill, (low, high))| (skill.clone(), rng.rank_between(*low, *high)))
+                        .collect();
+                    names.push(name.clone());
+                    expanded.push(Task::Baseline {
+                        name,
+                        template: template.clone(),
+                        skills,
+                    });
+                }
+                expanded.push(Task::Batch { names, tasks });
+                for task in expanded.into_iter().rev() {
+                    queue.push_front(task);
+                }
+            }
+            Task::Compound {
+                name,
+                primary,
+                also,
+            } => {
+                persons
+                    .get_mut(&name)
+                    .unwrap()
+                    .compound
+                    .insert(primary, also);
+            }
+            Task::Pin {
+                name,
+                segment,
+                combo,
+                hours,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.pin.insert((segment, normalize_combo(combo)), hours);
+            }
+            Task::MaxConcurrent { name, n } => {
+                // With nothing allowed, no target could ever be met.
+                if n == 0 {
+                    panic!("{} can't train at most 0 skills at once", name);
+                }
+                persons.get_mut(&name).unwrap().max_concurrent = Some(n);
+            }
+            Task::Deprioritize { name, skill } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.preference.insert(skill, DEPRIORITIZED_PREFERENCE);
+            }
+            Task::Prefer {
+                name,
+                skill,
+                weight,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                *person.preference.entry(skill).or_insert(1.0) *= weight;
+            }
+            Task::Observe { name, metric } => {
+                persons.get_mut(&name).unwrap().observe.insert(metric);
+            }
+            Task::Quality { name, quality } => {
+                persons.get_mut(&name).unwrap().quality = quality;
+            }
+            Task::RenameSkill { from, to } => {
+                for person in persons.values_mut() {
+                    rename_skill(person, &from, &to, &mut summary.warnings);
+                }
+            }
+            Task::Swap {
+                name,
+                a,
+                b,
+                carry_progress,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                let progress = |person: &Person, skill: &str| {
+                    person.target.get(skill).map_or(0.0, |target| {
+                        let rank = person.skills[skill];
+                        let bands = Bands::of(person, skill);
+                        effective_training_hours_needed(skill, bands, rank, target.target_rank)
+                            - target.hours_needed
+                    })
+                };
+                let (progress_a, progress_b) = (progress(person, &a), progress(person, &b));
+                let target_a = person.target.remove(&a);
+                let target_b = person.target.remove(&b);
+                for (skill, target, progress) in
+                    [(a, target_b, progress_b), (b, target_a, progress_a)]
+                {
+                    let Some(Target { target_rank, .. }) = target else {
+                        continue;
+                    };
+                    let Some(rank) = person.skills.get(&skill).copied() else {
+                        panic!("{} can't swap a target onto unknown skill {}", name, skill);
+                    };
+                    let bands = Bands::of(person, &skill);
+                    let mut hours_needed =
+                        effective_training_hours_needed(&skill, bands, rank, target_rank);
+                    if carry_progress {
+                        hours_needed = (hours_needed - progress).max(0.0);
+                    }
+                    person.target.insert(
+                        skill,
+                        Target {
+                            target_rank,
+                            hours_needed,
+                        },
+                    );
+                }
+            }
+            Task::Requirement {
+                name,
+                skill,
+                rank,
+                not_before,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.pending.insert(skill, (rank, not_before));
+            }
+            Task::Boost {
+                name,
+                combo,
+                multiplier,
+                until,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                let combo = combo.map(normalize_combo);
+                person.boosts.push(Boost {
+                    combo,
+                    multiplier,
+                    until,
+                });
+            }
+            Task::Constraint {
+                name,
+                terms,
+                op,
+                rhs,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.constraints.push(LinearConstraint { terms, op, rhs });
+            }
+            Task::Report { names } => {
+                let reported: Vec<&Person> = match names {
+                    Some(names) => names.iter().map(|name| &persons[name]).collect(),
+                    None => persons.values().collect(),
+                };
+                summary.print_snapshot(now, &reported, options.precision);
+            }
+            Task::RankStep { name, step } => {
+                let person = persons.get_mut(&name).unwrap();
+                for (skill, step) in step {
+                    if step <= 0.0 {
+                        panic!("{} has a non-positive rank step for {}", name, skill);
+                    }
+                    person.rank_step.insert(skill, step);
+                }
+            }
+            Task::Respec {
+                name,
+                from,
+                to,
+                ranks,
+                ratio,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                for skill in [&from, &to] {
+                    if !person.skills.contains_key(skill) {
+                        panic!("{} can't respec unknown skill {}", name, skill);
+                    }
+                }
+                // Give up ranks of one skill, no further than rank 0...
+                let old = current_rank(person, &from);
+                let new = (old - ranks).max(0.0);
+                let freed =
+                    effective_training_hours_needed(&from, Bands::of(person, &from), new, old);
+                person.set_rank(&from, new);
+                // ...and put a share of what they cost into the other, up to
+                // its target if it has one.
+                let mut rank = rank_after(person, &to, current_rank(person, &to), freed * ratio);
+                if let Some(target) = person.target.get(&to) {
+                    rank = rank.min(target.target_rank);
+                }
+                person.set_rank(&to, rank);
+                for skill in [from, to] {
+                    if let Some(target_rank) = person.target.get(&skill).map(|t| t.target_rank) {
+                        let target = new_target(person, &skill, target_rank);
+                        person.target.insert(skill, target);
+                    }
+                }
+            }
+            Task::Log { level } => log::set_max_level(level),
+            Task::DebugProblem { name, date } => {
+                persons.get_mut(&name).unwrap().debug_problem.insert(date);
+            }
+            Task::Mirror { follower, leader } => {
+                for name in [&follower, &leader] {
+                    if !persons.contains_key(name) {
+                        panic!("Cannot mirror unknown person {}", name);
+                    }
+                }
+                // Following in a circle would copy tasks forever.
+                let mut next = Some(&leader);
+                while let Some(name) = next {
+                    if *name == follower {
+                        panic!(
+                            "{} can't follow {}, who already follows them",
+                            follower, leader
+                        );
+                    }
+                    next = leaders.get(name);
+                }
+                leaders.insert(follower, leader);
+            }
+            Task::Availability { name, unavailable } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.unavailable = unavailable;
+                // Every pattern repeats within a couple of years, so someone
+                // unavailable for that long never will be, and the run would
+                // never finish.
+                if !(0..731).any(|days| person.available_on(now + chrono::Duration::days(days))) {
+                    panic!("{} is never available to train after {}", name, now);
+                }
+            }
+            Task::CramSchedule {
+                name,
+                segment,
+                base,
+                deadline,
+                max,
+            } => {
+                if deadline <= now {
+                    panic!(
+                        "{} can't cram for {} in {}; it's past",
+                        name, deadline, segment
+                    );
+                }
+                let cram = Cram {
+                    base,
+                    max,
+                    start: now,
+                    deadline,
+                };
+                persons.get_mut(&name).unwrap().cram.insert(segment, cram);
+            }
+            Task::Calendar { name, rules } => {
+                persons.get_mut(&name).unwrap().calendar = rules;
+            }
+            Task::Sabbatical {
+                name,
+                accrue_until,
+                spend_until,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.sabbatical = Some(Sabbatical {
+                    accrue_until,
+                    spend_until,
+                    banked: 0.0,
+                });
+            }
+            Task::Checkpoints {
+                name,
+                skill,
+                mut checkpoints,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                checkpoints.sort_by(|a, b| a.0.total_cmp(&b.0));
+                if let Some((rank, _)) = checkpoints.first() {
+                    let target = new_target(person, &skill, *rank);
+                    person.target.insert(skill.clone(), target);
+                }
+                person.checkpoints.insert(skill, checkpoints.into());
+            }
+            Task::Neglect {
+                name,
+                skill,
+                grace_days,
+                penalty,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.neglect.insert(
+                    skill,
+                    Neglect {
+                        grace_days,
+                        penalty,
+                        idle_days: 0,
+                    },
+                );
+            }
+            Task::Maintenance {
+                name,
+                skill,
+                hours_per_week,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                if !person.skills.contains_key(&skill) {
+                    panic!("{} can't maintain {}, which they don't have", name, skill);
+                }
+                if hours_per_week.is_nan() || hours_per_week < 0.0 {
+                    panic!(
+                        "{} has {} hours a week of upkeep for {}",
+                        name, hours_per_week, skill
+                    );
+                }
+                let maintenance = Maintenance {
+                    hours_per_week,
+                    done: VecDeque::new(),
+                };
+                person.maintenance.insert(skill, maintenance);
+            }
+            Task::TimeBudgetByWeek { name, weeks } => {
+                persons.get_mut(&name).unwrap().weekly_time = Some((now, weeks));
+            }
+            Task::LifetimeBudget { name, hours } => {
+                persons.get_mut(&name).unwrap().lifetime_budget = Some(hours);
+            }
+            Task::Tradeoff {
+                name,
+                first,
+                second,
+            } => {
+                if !options.validate_only {
+                    report_tradeoff(&persons, now, &name, &first, &second, options);
+                }
+            }
+            Task::Template { name, skills } => {
+                templates.insert(name, skills);
+            }
+            Task::Baseline {
+                name,
+                template,
+                mut skills,
+            } => {
+                if persons.contains_key(&name) {
                     panic!("Person already exists: {}", name);
                 }
-                persons.insert(name, Person::new(name, skills));
+                // Names differing only by case are almost always a typo.
+                if let Some(other) = persons
+                    .keys()
+                    .find(|p| p.to_lowercase() == name.to_lowercase())
+                {
+                    summary.warnings.push(Warning {
+                        kind: WarningKind::SimilarName,
+                        date: Some(now),
+                        name: Some(name.clone()),
+                        message: format!(
+                            "{} differs only by case from existing person {}",
+                            name, other
+                        ),
+                    });
+                }
+                if let Some(template) = template {
+                    let Some(base) = templates.get(&template) else {
+                        panic!("{} uses unknown template {}", name, template);
+                    };
+                    for (skill, rank) in base.iter() {
+                        skills.entry(skill.clone()).or_insert(*rank);
+                    }
+                }
+                if let Some((sigma, rng)) = jitter.as_mut() {
+                    for rank in skills.values_mut() {
+                        *rank = (*rank + *sigma * rng.normal()).max(0.0);
+                    }
+                }
+                persons.insert(
+                    name.clone(),
+                    Person::new(name, skills, options.priority_offset),
+                );
             }
             Task::Schedule { name, segment } => {
-                persons.get_mut(name).unwrap().schedule = segment;
+                persons.get_mut(&name).unwrap().schedule = segment;
             }
             Task::SafetyLimit { name, limit } => {
-                persons.get_mut(name).unwrap().safety_limit = limit;
+                persons.get_mut(&name).unwrap().safety_limit = limit;
             }
-            Task::ScheduleLimit { name, limit } => {
-                persons.get_mut(name).unwrap().schedule_limit = limit;
+            Task::ScheduleLimit { name, limit, focus } => {
+                let person = persons.get_mut(&name).unwrap();
+                for seg in focus.keys() {
+                    if !limit.contains_key(seg) {
+                        panic!(
+                            "{} has a focus multiplier for unlimited segment {}",
+                            name, seg
+                        );
+                    }
+                }
+                person.schedule_limit = limit;
+                person.limit_focus = focus;
             }
-            Task::Overlap { name, mut when } => {
-                let person = persons.get_mut(name).unwrap();
-                // Add the trivial 1-skill 'overlaps'.
+            Task::Overlap { name, when } => {
+                let person = persons.get_mut(&name).unwrap();
+                let mut when = normalize_overlaps(
+                    &name,
+                    expand_overlap_wildcards(&person.skills, when),
+                    &mut summary.warnings,
+                );
+                // Add the trivial 1-skill 'overlaps', unless given explicitly.
                 for skill in person.skills.keys() {
-                    when.push(Overlap {
-                        combo: vec![skill],
-                        bonus: 1.0,
-                    });
+                    if !when.iter().any(|o| o.combo == std::slice::from_ref(skill)) {
+                        when.push(Overlap {
+                            combo: vec![skill.clone()],
+                            bonus: 1.0,
+                            segment_bonus: btreemap! {},
+                            min_rank: btreemap! {},
+                        });
+                    }
                 }
                 person.overlap = when;
             }
+            Task::Pair {
+                name,
+                skills,
+                bonus,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                let mut when = std::mem::take(&mut person.overlap);
+                when.extend(pair_overlaps(&skills, bonus));
+                person.overlap = normalize_overlaps(&name, when, &mut summary.warnings);
+            }
+            Task::Focus {
+                name,
+                skill,
+                multiplier,
+                until,
+            } => {
+                let person = persons.get_mut(&name).unwrap();
+                person.focus.insert(skill, Focus { multiplier, until });
+            }
+            Task::GroupTarget {
+                names,
+                skill,
+                combined_rank,
+            } => {
+                let group = GroupTarget {
+                    names,
+                    skill: skill.clone(),
+                    combined_rank,
+                };
+                let gap = combined_rank - group.combined(&persons);
+                if gap <= 0.0 {
+                    println!(
+                        "{}: {} already have a combined rank of {} in {}",
+                        now,
+                        group.names.join(" + "),
+                        combined_rank,
+                        skill
+                    );
+                    continue;
+                }
+                for name in group.names.iter() {
+                    let person = persons.get_mut(name).unwrap();
+                    let alone = person.skills.get(&skill).copied().unwrap_or(0.0) + gap;
+                    // Don't cut short a higher target of their own.
+                    if person
+                        .target
+                        .get(&skill)
+                        .is_none_or(|t| t.target_rank < alone)
+                    {
+                        let target = new_target(person, &skill, alone);
+                        person.target.insert(skill.clone(), target);
+                    }
+                }
+                groups.push(group);
+            }
+            Task::HoursTarget { name, skill, hours } => {
+                if hours.is_nan() || hours <= 0.0 {
+                    panic!("{} has a target of {} hours for {}", name, hours, skill);
+                }
+                let person = persons.get_mut(&name).unwrap();
+                // Whatever rank the hours land on is the target rank, so
+                // that completion sets it like any other.
+                let rank = person.skills.get(&skill).copied().unwrap_or(0.0);
+                let target_rank = rank_after(person, &skill, rank, hours);
+                let mut target = new_target(person, &skill, target_rank);
+                target.hours_needed = hours;
+                person.checkpoints.remove(&skill);
+                person.target.insert(skill, target);
+            }
             Task::Target { name, target } => {
-                let person = persons.get_mut(name).unwrap();
+                let person = persons.get_mut(&name).unwrap();
                 let mut new_targets = btreemap! {};
                 for (skill, target_rank) in target {
-                    new_targets.insert(
-                        skill,
-                        Target {
-                            target_rank,
-                            hours_needed: effective_training_hours_needed(
-                                skill,
-                                person.skills[skill],
-                                target_rank,
-                            ),
-                        },
-                    );
+                    let target = new_target(person, &skill, target_rank);
+                    new_targets.insert(skill, target);
                 }
                 person.target = new_targets;
+                person.checkpoints.clear();
             }
         }
     }
+    if let Some(path) = options.graph.as_ref() {
+        let mut file = std::fs::File::create(path).expect("Failed to create graph file");
+        report::write_graph(persons.values(), &mut file).expect("Failed to write graph");
+    }
+    if options.validate_only {
+        summary.problems = persons
+            .values()
+            .flat_map(|person| validate_person(person, options))
+            .collect();
+        return summary;
+    }
+    if options.capacity {
+        for person in persons.values().filter(|p| !p.target.is_empty()) {
+            println!(
+                "{}: {} can train at most {:.*} effective hours per day",
+                now,
+                person.name,
+                options.precision,
+                daily_capacity(person, now, options)
+            );
+        }
+    }
+    if let Some((name, [first, second], bonus)) = options.try_overlap.as_ref() {
+        let Some(person) = persons.get(name) else {
+            panic!("--try-overlap: unknown person {}", name);
+        };
+        let overlap = Overlap {
+            combo: normalize_combo(vec![first.clone(), second.clone()]),
+            bonus: *bonus,
+            segment_bonus: btreemap! {},
+            min_rank: btreemap! {},
+        };
+        for skill in overlap.combo.iter() {
+            if !person.skills.contains_key(skill) {
+                panic!("--try-overlap: {} doesn't have {}", name, skill);
+            }
+        }
+        println!(
+            "{}: {} + {} at {}x would change {}'s ROI today by {:+.*}",
+            now,
+            first,
+            second,
+            bonus,
+            name,
+            options.precision,
+            try_overlap(person, now, overlap, options)
+        );
+    }
+    for person in persons.values() {
+        let mut warn = |kind, message| {
+            summary.warnings.push(Warning {
+                kind,
+                date: Some(now),
+                name: Some(person.name.clone()),
+                message,
+            })
+        };
+        for problem in unknown_segments(person) {
+            warn(WarningKind::UnknownSegment, problem);
+        }
+        if let Some(days) = estimate_min_days(person) {
+            if days > options.max_days as f32 {
+                warn(
+                    WarningKind::SlowTargets,
+                    format!(
+                        "{} needs at least {:.0} days to reach their targets, more than {}",
+                        person.name, days, options.max_days
+                    ),
+                );
+            }
+        }
+        for problem in unreachable_checkpoints(person, now, options) {
+            warn(WarningKind::UnreachableCheckpoint, problem);
+        }
+        // Upkeep gets time first, so with too much of it nothing else ever
+        // would, and the run would never finish.
+        let upkeep: f32 = person
+            .maintenance
+            .values()
+            .map(|m| m.hours_per_week / 7.0)
+            .sum();
+        let time: f32 = person.schedule_on(now).values().sum();
+        if !person.target.is_empty() && upkeep > 0.0 && upkeep >= time {
+            panic!(
+                "{} needs {:.*} hours of upkeep a day, leaving nothing of their {:.*} hours to train",
+                person.name, options.precision, upkeep, options.precision, time
+            );
+        }
+    }
     // At the end of the schedule.
     // Run the simulator until no-one has any skill-up targets left.
-    let mut sum_roi = 0.0;
-    let mut sum_wasted_time = 0.0;
-    let mut days = 0;
-    while persons.iter().any(|(_, person)| person.target.len() > 0) {
-        let (day_roi, day_wt) = simulate_day(&mut persons, now);
-        sum_roi += day_roi;
-        sum_wasted_time += day_wt;
-        days += 1;
+    while persons
+        .iter()
+        .any(|(_, person)| !person.target.is_empty() || !person.pending.is_empty())
+    {
+        let mut results = simulate_day(&mut persons, now, options);
+        complete_group_targets(&mut groups, &mut persons, now, &mut results, options);
+        if !options.quiet {
+            report::print_day(&results, options);
+        }
+        summary.add_day(now, &results);
+        summary.observe(&persons, &results);
+        // Days with no time to train at all say nothing about progress.
+        let day_roi: f32 = results.iter().map(|r| r.roi).sum();
+        let available: f32 = results
+            .iter()
+            .flat_map(|r| r.segment_usage.values())
+            .map(|(_, available)| available)
+            .sum();
+        // Nor does the day the last target was met, as it's usually short.
+        let remaining = persons
+            .values()
+            .any(|person| !person.target.is_empty() || !person.pending.is_empty());
+        if remaining
+            && options
+                .min_daily_roi
+                .is_some_and(|min| available > 0.0 && day_roi < min)
+        {
+            println!(
+                "{}: Stopping, as ROI {:.*} fell below the minimum",
+                now, options.precision, day_roi
+            );
+            for person in persons.values() {
+                for skill in person.target.keys().chain(person.pending.keys()) {
+                    summary.unmet.push((person.name.clone(), skill.clone()));
+                }
+            }
+            summary.stopped_early = true;
+            break;
+        }
         now = now.succ_opt().unwrap();
     }
+    summary.note_unused_overlaps(persons.values());
+    summary.note_final_ranks(persons.values());
+    summary
+}
+
+// Whether the skill is one we know how to train.
+fn is_known_skill(skill: &str) -> bool {
+    ATTRIBUTES.contains(skill) || ABILITIES.contains(skill) || PSIONICS.contains(skill)
+}
+
+// Preferences and focus multipliers that would make the objective meaningless:
+// NaN, infinite, or negative unless that's allowed.
+fn preference_problems(person: &Person, allow_negative: bool) -> Vec<String> {
+    let weights = person
+        .preference
+        .iter()
+        .map(|(skill, weight)| (skill, "preference", *weight))
+        .chain(
+            person
+                .focus
+                .iter()
+                .map(|(skill, focus)| (skill, "focus multiplier", focus.multiplier)),
+        );
+    let mut problems = vec![];
+    for (skill, what, weight) in weights {
+        if !weight.is_finite() {
+            problems.push(format!(
+                "{} has a {} {} for {}",
+                person.name, what, weight, skill
+            ));
+        } else if weight < 0.0 && !allow_negative {
+            problems.push(format!(
+                "{} has a negative {} {} for {}; pass --allow-negative-preference if that's intended",
+                person.name, what, weight, skill
+            ));
+        }
+    }
+    problems
+}
+
+// Checks a person's setup for mistakes that would otherwise only show up,
+// if at all, partway through a simulation.
+fn validate_person(person: &Person, options: &Options) -> Vec<String> {
+    let mut problems = preference_problems(person, options.allow_negative_preference);
+    let name = &person.name;
+    for skill in person.skills.keys() {
+        if !is_known_skill(skill) {
+            problems.push(format!("{} has unknown skill {}", name, skill));
+        }
+    }
+    let calendar_time = person.calendar.iter().any(|rule| rule.hours > 0.0)
+        || person
+            .cram
+            .values()
+            .any(|cram| cram.base.max(cram.max) > 0.0);
+    if !person.target.is_empty() && person.schedule.values().all(|d| *d <= 0.0) && !calendar_time {
+        problems.push(format!("{} has targets but no time to train them", name));
+    }
+    for overlap in person.overlap.iter() {
+        for skill in overlap.combo.iter() {
+            if !person.skills.contains_key(skill) {
+                problems.push(format!(
+                    "{} has overlap {:?} with a skill they don't have: {}",
+                    name, overlap.combo, skill
+                ));
+            }
+        }
+    }
+    for overlap in person.overlap.iter().filter(|o| o.combo.len() > 1) {
+        let preferences: Vec<f32> = overlap
+            .combo
+            .iter()
+            .map(|skill| person.preference.get(skill).copied().unwrap_or(1.0))
+            .collect();
+        let average = preferences.iter().sum::<f32>() / preferences.len() as f32;
+        let highest = preferences.iter().cloned().fold(f32::MIN, f32::max);
+        if overlap.bonus * average <= highest {
+            problems.push(format!(
+                "{} has overlap {:?} whose bonus {} is too small to matter next to their preferences",
+                name, overlap.combo, overlap.bonus
+            ));
+        }
+    }
+    problems.extend(unknown_segments(person));
+    for (_, combo) in person.pin.keys() {
+        if !person.overlap.iter().any(|o| o.combo == *combo) {
+            problems.push(format!(
+                "{} has a pin on {:?}, which is not a defined overlap",
+                name, combo
+            ));
+        }
+    }
+    problems
+}
+
+// Finds segments referenced by a person's limits, quality or pins that aren't
+// in their schedule. These are silently ignored, so they're likely typos.
+fn unknown_segments(person: &Person) -> Vec<String> {
+    let name = &person.name;
+    let mut problems = vec![];
+    let mut check = |seg: &str, what: &str| {
+        let in_calendar = person.calendar.iter().any(|rule| rule.segment == seg);
+        if !person.schedule.contains_key(seg) && !person.cram.contains_key(seg) && !in_calendar {
+            problems.push(format!("{} has {} in unknown segment {}", name, what, seg));
+        }
+    };
+    for seg in person.schedule_limit.keys() {
+        check(seg, "a schedule limit");
+    }
+    for seg in person.quality.keys() {
+        check(seg, "a training quality");
+    }
+    for (seg, _) in person.pin.keys() {
+        check(seg, "a pin");
+    }
+    for overlap in person.overlap.iter() {
+        for seg in overlap.segment_bonus.keys() {
+            check(seg, "an overlap bonus");
+        }
+    }
+    problems
+}
+
+// The most effective hours a person's schedule and overlaps could yield in a
+// day, if none of their targets were ever reached.
+fn daily_capacity(person: &Person, now: NaiveDate, options: &Options) -> f32 {
+    let mut unlimited = person.clone();
+    for target in unlimited.target.values_mut() {
+        target.hours_needed = 1e9;
+    }
+    unlimited.lifetime_budget = None;
+    let plain = Options {
+        balanced: false,
+        maxmin: false,
+        steps_per_day: 1,
+        ..options.clone()
+    };
+    simulate_person(&now, &unlimited, &plain).total_roi
+}
+
+// The most effective hours a skill could get in a day if nothing else
+// needed training, within all of the person's limits.
+fn skill_capacity(person: &Person, skill: &str, now: NaiveDate, options: &Options) -> f32 {
+    let mut unlimited = person.clone();
+    unlimited.target.get_mut(skill).unwrap().hours_needed = 1e9;
+    unlimited.lifetime_budget = None;
+    let plain = Options {
+        steps_per_day: 1,
+        ..options.clone()
+    };
+    let stage = LexStage {
+        fixed: btreemap! {},
+        maximize: Some(skill.to_string()),
+    };
+    solve_person(
+        &now,
+        &unlimited,
+        &plain,
+        &Concurrency::default(),
+        Relaxation::None,
+        &stage,
+    )
+    .map_or(0.0, |(day, _)| day.increment[skill])
+}
+
+// Checkpoints that can't be reached by their deadline even if their skill
+// got the best day it possibly could, every day from now on.
+fn unreachable_checkpoints(person: &Person, now: NaiveDate, options: &Options) -> Vec<String> {
+    let mut problems = vec![];
+    for (skill, checkpoints) in person.checkpoints.iter() {
+        let Some(target) = person.target.get(skill) else {
+            continue;
+        };
+        let capacity = skill_capacity(person, skill, now, options);
+        let bands = Bands::of(person, skill);
+        for (rank, deadline) in checkpoints.iter() {
+            let hours = target.hours_needed
+                + effective_training_hours_needed(skill, bands, target.target_rank, *rank);
+            let days = (*deadline - now).num_days() + 1;
+            if capacity <= EPSILON {
+                problems.push(format!(
+                    "{} can't reach {} {} by {}: it can't be trained at all",
+                    person.name, skill, rank, deadline
+                ));
+            } else if hours > capacity * days as f32 + EPSILON {
+                problems.push(format!(
+                    "{} can't reach {} {} by {}: even {:.*} hours a day would take {:.0} days, not {}",
+                    person.name,
+                    skill,
+                    rank,
+                    deadline,
+                    options.precision,
+                    capacity,
+                    (hours / capacity).ceil(),
+                    days
+                ));
+            }
+        }
+    }
+    problems
+}
+
+// A lower bound on the days needed to reach all targets: the hours they need,
+// over what a day would yield if every segment went to its best combo.
+// Safety limits and diminishing targets only make it take longer. Calendar
+// time is counted as if it came every day, and cramming as if always at its
+// peak, which keeps it a lower bound.
+fn estimate_min_days(person: &Person) -> Option<f32> {
+    if person.target.is_empty() {
+        return None;
+    }
+    let hours_needed: f32 = person.target.values().map(|t| t.hours_needed).sum();
+    let mut schedule = person.schedule.clone();
+    for (seg, cram) in person.cram.iter() {
+        schedule.insert(seg.clone(), cram.base.max(cram.max));
+    }
+    for rule in person.calendar.iter() {
+        *schedule.entry(rule.segment.clone()).or_insert(0.0) += rule.hours;
+    }
+    let mut best_daily_roi = 0.0;
+    for (seg, duration) in schedule.iter() {
+        let allowed = person.schedule_limit.get(seg);
+        let best = person
+            .overlap
+            .iter()
+            .filter(|o| allowed.is_none_or(|a| o.combo.iter().all(|s| a.contains(s))))
+            .map(|o| {
+                let targeted = o.combo.iter().filter(|s| person.target.contains_key(*s));
+                o.bonus_for(seg, &person.skills)
+                    * person.quality_in(seg)
+                    * person.limit_focus_in(seg)
+                    * targeted.count() as f32
+                    / o.combo.len() as f32
+            })
+            .fold(0.0, f32::max);
+        best_daily_roi += duration.max(0.0) * best;
+    }
+    Some(hours_needed / best_daily_roi)
+}
+
+// Combos are kept sorted and free of repeats, so that the same skills always
+// make the same combo, no matter how they were written.
+fn normalize_combo(mut combo: Vec<Skill>) -> Vec<Skill> {
+    combo.sort();
+    combo.dedup();
+    combo
+}
+
+// A name for a combo that doesn't depend on the order its skills were given
+// in, for use in LP variable names.
+fn combo_name(combo: &[Skill]) -> String {
+    normalize_combo(combo.to_vec()).join("_")
+}
+
+// Normalizes each combo, and drops combos that repeat an earlier one,
+// warning about them. Otherwise the same skills in a different order would
+// get their own variables, and only the first bonus would ever be used.
+fn normalize_overlaps(name: &str, when: Vec<Overlap>, warnings: &mut Vec<Warning>) -> Vec<Overlap> {
+    let mut normalized: Vec<Overlap> = vec![];
+    for mut overlap in when {
+        overlap.combo = normalize_combo(overlap.combo);
+        match normalized.iter().find(|o| o.combo == overlap.combo) {
+            Some(existing) => warnings.push(Warning {
+                kind: WarningKind::DuplicateOverlap,
+                date: None,
+                name: Some(name.to_string()),
+                message: format!(
+                    "{} has overlap {:?} defined more than once; using bonus {} and ignoring {}",
+                    name, overlap.combo, existing.bonus, overlap.bonus
+                ),
+            }),
+            None => normalized.push(overlap),
+        }
+    }
+    normalized
+}
+
+// A small seeded generator (SplitMix64), so that generated cohorts come out
+// the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A sample from the uniform distribution over [0, 1).
+    fn uniform(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // A rank in the given range, rounded to the nearest half rank.
+    fn rank_between(&mut self, low: f32, high: f32) -> f32 {
+        let fraction = self.uniform();
+        let rank = low + (high - low) * fraction;
+        ((rank * 2.0).round() / 2.0).clamp(low, high)
+    }
+
+    // A sample from the standard normal distribution, by Box-Muller.
+    fn normal(&mut self) -> f32 {
+        let mut uniform = || ((self.next_u64() >> 40) as f32 + 1.0) / ((1u64 << 24) as f32 + 1.0);
+        let (u1, u2) = (uniform(), uniform());
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+    }
+}
+
+// Re-solves a person's day with every preference nudged by a little seeded
+// noise, and warns if the ROI moves by more than can be put down to there
+// being many equally good plans.
+fn perturb_test(
+    person: &Person,
+    now: NaiveDate,
+    roi: f32,
+    runs: u32,
+    options: &Options,
+) -> Option<Warning> {
+    use chrono::Datelike;
+    let mut rng = Rng(options.seed ^ now.num_days_from_ce() as u64);
+    let (mut low, mut high) = (roi, roi);
+    for _ in 0..runs {
+        let mut perturbed = person.clone();
+        for skill in person.target.keys() {
+            let noise = 1.0 + PERTURBATION * (2.0 * rng.uniform() - 1.0);
+            *perturbed.preference.entry(skill.clone()).or_insert(1.0) *= noise;
+        }
+        let roi = simulate_person(&now, &perturbed, options).total_roi;
+        low = low.min(roi);
+        high = high.max(roi);
+    }
+    (high - low > PERTURB_TOLERANCE * roi.abs().max(1.0)).then(|| Warning {
+        kind: WarningKind::UnstableRoi,
+        date: Some(now),
+        name: Some(person.name.clone()),
+        message: format!(
+            "{}'s ROI ranges from {:.*} to {:.*} under tiny changes to the objective",
+            person.name, options.precision, low, options.precision, high
+        ),
+    })
+}
+
+// Moves a map entry from one skill to another, if there is one.
+fn rename_key<V>(map: &mut BTreeMap<Skill, V>, from: &str, to: &str) {
+    if let Some(value) = map.remove(from) {
+        map.insert(to.to_string(), value);
+    }
+}
+
+// Renames a skill everywhere a person refers to it. Combos are renormalized
+// afterwards, since the new name may sort differently.
+fn rename_skill(person: &mut Person, from: &str, to: &str, warnings: &mut Vec<Warning>) {
+    let rename = |skill: &mut Skill| {
+        if *skill == from {
+            *skill = to.to_string();
+        }
+    };
+    rename_key(&mut person.skills, from, to);
+    rename_key(&mut person.safety_limit, from, to);
+    rename_key(&mut person.rank_step, from, to);
+    rename_key(&mut person.peak, from, to);
+    rename_key(&mut person.target, from, to);
+    rename_key(&mut person.pending, from, to);
+    rename_key(&mut person.maintenance, from, to);
+    rename_key(&mut person.checkpoints, from, to);
+    rename_key(&mut person.neglect, from, to);
+    rename_key(&mut person.preference, from, to);
+    rename_key(&mut person.focus, from, to);
+    rename_key(&mut person.compound, from, to);
+    for spillover in person.compound.values_mut() {
+        rename_key(spillover, from, to);
+    }
+    for allowed in person.schedule_limit.values_mut() {
+        allowed.iter_mut().for_each(rename);
+    }
+    let mut overlap = std::mem::take(&mut person.overlap);
+    for o in overlap.iter_mut() {
+        o.combo.iter_mut().for_each(rename);
+        rename_key(&mut o.min_rank, from, to);
+    }
+    person.overlap = normalize_overlaps(&person.name, overlap, warnings);
+    for boost in person.boosts.iter_mut() {
+        if let Some(combo) = boost.combo.as_mut() {
+            combo.iter_mut().for_each(rename);
+            *combo = normalize_combo(std::mem::take(combo));
+        }
+    }
+    for constraint in person.constraints.iter_mut() {
+        for (skill, _) in constraint.terms.iter_mut() {
+            rename(skill);
+        }
+    }
+    person.pin = std::mem::take(&mut person.pin)
+        .into_iter()
+        .map(|((seg, mut combo), hours)| {
+            combo.iter_mut().for_each(rename);
+            ((seg, normalize_combo(combo)), hours)
+        })
+        .collect();
+}
+
+// Looks up the skill category named by a wildcard such as "@ATTRIBUTES".
+fn skill_category(wildcard: &str) -> Option<&'static BTreeSet<&'static str>> {
+    match wildcard {
+        "@ATTRIBUTES" => Some(&ATTRIBUTES),
+        "@ABILITIES" => Some(&ABILITIES),
+        "@PSIONICS" => Some(&PSIONICS),
+        _ => None,
+    }
+}
+
+// Expands overlaps whose combos contain category wildcards into concrete
+// combos over the skills the person actually has.
+// Concrete combos always win over wildcard-generated ones for the same skills.
+fn expand_overlap_wildcards(skills: &BTreeMap<Skill, f32>, when: Vec<Overlap>) -> Vec<Overlap> {
+    let (wildcards, mut expanded): (Vec<Overlap>, Vec<Overlap>) = when
+        .into_iter()
+        .partition(|o| o.combo.iter().any(|skill| skill.starts_with('@')));
+    let mut seen: BTreeSet<BTreeSet<Skill>> = expanded
+        .iter()
+        .map(|o| o.combo.iter().cloned().collect())
+        .collect();
+    for overlap in wildcards {
+        // The candidate skills for each position in the combo.
+        let slots: Vec<Vec<Skill>> = overlap
+            .combo
+            .iter()
+            .map(|skill| match skill_category(skill) {
+                Some(category) => skills
+                    .keys()
+                    .filter(|s| category.contains(s.as_str()))
+                    .cloned()
+                    .collect(),
+                None if skill.starts_with('@') => panic!("Unknown skill category: {}", skill),
+                None => vec![skill.clone()],
+            })
+            .collect();
+        let mut combos: Vec<Vec<Skill>> = vec![vec![]];
+        for slot in slots {
+            let mut next = vec![];
+            for combo in combos.iter() {
+                for skill in slot.iter() {
+                    if !combo.contains(skill) {
+                        let mut combo = combo.clone();
+                        combo.push(skill.clone());
+                        next.push(combo);
+                    }
+                }
+            }
+            combos = next;
+        }
+        for mut combo in combos {
+            combo.sort();
+            if seen.insert(combo.iter().cloned().collect()) {
+                expanded.push(Overlap {
+                    combo,
+                    bonus: overlap.bonus,
+                    segment_bonus: overlap.segment_bonus.clone(),
+                    min_rank: overlap.min_rank.clone(),
+                });
+            }
+        }
+    }
+    expanded
+}
+
+fn simulate_day(
+    persons: &mut BTreeMap<Name, Person>,
+    now: NaiveDate,
+    options: &Options,
+) -> Vec<PersonDayResult> {
+    info!("Date: {}", now);
+    let mut results = vec![];
+    // Requirements whose date has come are costed from today's ranks.
+    for person in persons.values_mut() {
+        let due: Vec<Skill> = person
+            .pending
+            .iter()
+            .filter(|(_, (_, not_before))| *not_before <= now)
+            .map(|(skill, _)| skill.clone())
+            .collect();
+        for skill in due {
+            let (rank, _) = person.pending.remove(&skill).unwrap();
+            let target = new_target(person, &skill, rank);
+            person.target.insert(skill, target);
+        }
+    }
+    // Only persons with outstanding targets or upkeep need a plan for the day.
+    for (_, person) in persons
+        .iter_mut()
+        .filter(|(_, p)| !p.target.is_empty() || !p.maintenance.is_empty())
+    {
+        let mut result = PersonDayResult {
+            name: person.name.clone(),
+            date: now,
+            roi: 0.0,
+            wasted_time: 0.0,
+            hours: BTreeMap::new(),
+            ranks: BTreeMap::new(),
+            segment_usage: BTreeMap::new(),
+            completed: vec![],
+            abandoned: vec![],
+            combo_hours: BTreeMap::new(),
+            solver_status: None,
+            relaxed: vec![],
+            checkpoints: vec![],
+            penalties: vec![],
+            warnings: vec![],
+            disagreement: 0.0,
+            maintenance: BTreeMap::new(),
+            decay: BTreeMap::new(),
+        };
+        // The day is planned in steps, each with an equal share of the
+        // schedule, so that time freed up by a target finishing partway
+        // through the day goes to the others.
+        for _ in 0..options.steps_per_day {
+            if person.target.is_empty() && person.maintenance.is_empty() {
+                break;
+            }
+            let day = simulate_person(&now, person, options);
+            if let Some(runs) = options.perturb_test {
+                result
+                    .warnings
+                    .extend(perturb_test(person, now, day.total_roi, runs, options));
+            }
+            // Training some skills also partially trains related ones.
+            let mut increment = day.increment.clone();
+            for (primary, hours) in day.increment.iter() {
+                for (skill, fraction) in person.compound.get(primary).into_iter().flatten() {
+                    if person.target.contains_key(skill) {
+                        *increment.entry(skill.clone()).or_insert(0.0) += hours * fraction;
+                    }
+                }
+            }
+            for (skill, effective_hours_trained) in increment.iter() {
+                *result.ranks.entry(skill.clone()).or_insert(0.0) +=
+                    advance_rank(person, skill, *effective_hours_trained);
+                *result.hours.entry(skill.clone()).or_insert(0.0) += effective_hours_trained;
+                // Completion is final: the target is removed here, and only
+                // a new Target task can bring it back. A skill with more
+                // checkpoints moves straight on to the next one, though.
+                if person.target[skill].hours_needed <= EPSILON {
+                    person.set_rank(skill, person.target[skill].target_rank);
+                    person.target.remove(skill);
+                    result.completed.push((skill.clone(), person.skills[skill]));
+                    let queue = person.checkpoints.get_mut(skill);
+                    if let Some((rank, deadline)) = queue.and_then(|q| q.pop_front()) {
+                        result.checkpoints.push((skill.clone(), rank, deadline));
+                    }
+                    if let Some((rank, _)) = person.checkpoints.get(skill).and_then(|q| q.front()) {
+                        let target = new_target(person, skill, *rank);
+                        person.target.insert(skill.clone(), target);
+                    }
+                }
+            }
+            // Once the lifetime budget runs out, whatever is left can never be met.
+            if let Some(budget) = person.lifetime_budget.as_mut() {
+                *budget -= day.total_roi;
+                if *budget <= EPSILON {
+                    result.abandoned = person.target.keys().cloned().collect();
+                    person.target.clear();
+                }
+            }
+            // Time left unused while on sabbatical is banked, to be spent later.
+            if let Some(sabbatical) = person.sabbatical.as_mut() {
+                if now <= sabbatical.accrue_until {
+                    sabbatical.banked += day.wasted_time;
+                }
+            }
+            for (seg, used) in day.segment_usage.iter() {
+                if *seg == BANKED_SEGMENT {
+                    person.sabbatical.as_mut().unwrap().banked -= used;
+                    continue;
+                }
+                let available = person.schedule_on(now)[seg] * person.time_scale_on(now);
+                result
+                    .segment_usage
+                    .entry(seg.clone())
+                    .or_insert((0.0, available))
+                    .0 += used;
+            }
+            result.roi += day.total_roi;
+            result.wasted_time += day.wasted_time;
+            for (combo, hours) in day.combo_hours {
+                *result.combo_hours.entry(combo).or_insert(0.0) += hours;
+            }
+            result.relaxed.extend(day.relaxed);
+            result.disagreement = result.disagreement.max(day.disagreement);
+            for (skill, hours) in day.maintenance {
+                *result.maintenance.entry(skill).or_insert(0.0) += hours;
+            }
+            if day.status != solvers::Status::Optimal {
+                result.solver_status = Some(format!("{:?}", day.status));
+            }
+        }
+        // Upkeep that fell short over the last week is lost from the skill:
+        // from the progress towards its target, if it has one.
+        for (skill, maintenance) in person.maintenance.iter_mut() {
+            let done = result.maintenance.get(skill).copied().unwrap_or(0.0);
+            let lost = maintenance.record(done);
+            if lost > EPSILON {
+                result.decay.insert(skill.clone(), lost);
+            }
+        }
+        for (skill, lost) in result.decay.iter() {
+            match person.target.get_mut(skill) {
+                Some(target) => target.hours_needed += lost,
+                None => {
+                    let rank = person.skills[skill];
+                    let cost = Bands::of(person, skill).cost(skill, rank);
+                    person.set_rank(skill, (rank - lost / cost).max(0.0));
+                }
+            }
+        }
+        // Skills that go untrained for too long cost something every day after.
+        for (skill, neglect) in person.neglect.iter_mut() {
+            if result
+                .hours
+                .get(skill)
+                .is_some_and(|hours| *hours > EPSILON)
+            {
+                neglect.idle_days = 0;
+            } else {
+                neglect.idle_days += 1;
+                if neglect.idle_days > neglect.grace_days {
+                    result.penalties.push((skill.clone(), neglect.penalty));
+                }
+            }
+        }
+        results.push(result);
+    }
+    results
+}
+
+// Sets up a target rank for a skill, costed from the skill's current rank.
+// A skill the person doesn't have yet starts from rank 0, and needs its
+// trivial overlap so it can be trained at all.
+fn new_target(person: &mut Person, skill: &str, target_rank: f32) -> Target {
+    if !person.skills.contains_key(skill) {
+        person.skills.insert(skill.to_string(), 0.0);
+        person.overlap.push(Overlap {
+            combo: vec![skill.to_string()],
+            bonus: 1.0,
+            segment_bonus: btreemap! {},
+            min_rank: btreemap! {},
+        });
+    }
+    Target {
+        target_rank,
+        hours_needed: effective_training_hours_needed(
+            skill,
+            Bands::of(person, skill),
+            person.skills[skill],
+            target_rank,
+        ),
+    }
+}
+
+// A target shared by several persons, from Task::GroupTarget.
+#[derive(Debug, Clone)]
+struct GroupTarget {
+    names: Vec<Name>,
+    skill: Skill,
+    combined_rank: f32,
+}
+
+impl GroupTarget {
+    // The participants' ranks in the skill, added up, including progress
+    // into their current bands.
+    fn combined(&self, persons: &BTreeMap<Name, Person>) -> f32 {
+        self.names
+            .iter()
+            .map(|name| {
+                let Some(person) = persons.get(name) else {
+                    panic!("Group target for unknown person {}", name);
+                };
+                match person.skills.contains_key(&self.skill) {
+                    true => current_rank(person, &self.skill),
+                    false => 0.0,
+                }
+            })
+            .sum()
+    }
+}
+
+// Ends the targets of every group whose combined rank has been reached, and
+// marks them completed in the day's results.
+fn complete_group_targets(
+    groups: &mut Vec<GroupTarget>,
+    persons: &mut BTreeMap<Name, Person>,
+    now: NaiveDate,
+    results: &mut [PersonDayResult],
+    options: &Options,
+) {
+    groups.retain(|group| {
+        let combined = group.combined(persons);
+        if combined + EPSILON < group.combined_rank {
+            return true;
+        }
+        for name in group.names.iter() {
+            let person = persons.get_mut(name).unwrap();
+            if !person.target.contains_key(&group.skill) {
+                continue;
+            }
+            // Progress into the current band would be lost with the target.
+            let rank = current_rank(person, &group.skill);
+            person.set_rank(&group.skill, rank);
+            person.target.remove(&group.skill);
+            if let Some(result) = results.iter_mut().find(|r| r.name == *name) {
+                result
+                    .completed
+                    .push((group.skill.clone(), person.skills[&group.skill]));
+            }
+        }
+        if !options.quiet {
+            println!(
+                "{}: {} reached a combined rank of {:.*} in {}",
+                now,
+                group.names.join(" + "),
+                options.precision,
+                combined,
+                group.skill
+            );
+        }
+        false
+    });
+}
+
+// Applies trained hours to a target one rank band at a time, since each band
+// costs more than the last. Crossing into a new band moves the skill up to it
+// and recomputes what's left from there. Returns the ranks gained.
+fn advance_rank(person: &mut Person, skill: &str, mut hours: f32) -> f32 {
+    let target_rank = person.target[skill].target_rank;
+    let bands = Bands::of(person, skill);
+    let mut gained = 0.0;
+    loop {
+        let rank = person.skills[skill];
+        let band_end = bands.next(rank, target_rank);
+        let beyond = effective_training_hours_needed(skill, bands, band_end, target_rank);
+        let target = person.target.get_mut(skill).unwrap();
+        let band_left = target.hours_needed - beyond;
+        if hours < band_left - EPSILON || band_end >= target_rank {
+            target.hours_needed -= hours;
+            return gained + hours / bands.cost(skill, rank);
+        }
+        hours -= band_left;
+        gained += band_left / bands.cost(skill, rank);
+        target.hours_needed = beyond;
+        person.skills.insert(skill.to_string(), band_end);
+    }
+}
+
+// A skill's rank including progress into the current band, which
+// person.skills only catches up with at the end of each band.
+fn current_rank(person: &Person, skill: &str) -> f32 {
+    let rank = person.skills[skill];
+    let Some(target) = person.target.get(skill) else {
+        return rank;
+    };
+    let bands = Bands::of(person, skill);
+    let band_end = bands.next(rank, target.target_rank);
+    let beyond = effective_training_hours_needed(skill, bands, band_end, target.target_rank);
+    band_end - (target.hours_needed - beyond) / bands.cost(skill, rank)
+}
+
+// The rank a skill would reach from a given rank with some effective hours.
+fn rank_after(person: &Person, skill: &str, mut rank: f32, mut hours: f32) -> f32 {
+    let bands = Bands::of(person, skill);
+    while hours > EPSILON {
+        let band_end = bands.next(rank, f32::INFINITY);
+        let cost = bands.cost(skill, rank);
+        if hours < cost * (band_end - rank) {
+            return rank + hours / cost;
+        }
+        hours -= cost * (band_end - rank);
+        rank = band_end;
+    }
+    rank
+}
+
+// Runs the simulation from the current state until the given skills all
+// reach their targets, returning when each got there. A skill that can't get
+// there has no date: its target was abandoned, or it still hadn't reached it
+// after --max-days, e.g. because a prerequisite never came or upkeep decay
+// outpaced training.
+fn completion_dates(
+    mut persons: BTreeMap<Name, Person>,
+    mut now: NaiveDate,
+    name: &str,
+    skills: &[&str],
+    options: &Options,
+) -> BTreeMap<Skill, Option<NaiveDate>> {
+    let mut dates = BTreeMap::new();
+    let end = now + chrono::Duration::days(options.max_days.into());
+    while dates.len() < skills.len() && now < end {
+        for result in simulate_day(&mut persons, now, options) {
+            if result.name != name {
+                continue;
+            }
+            for (skill, _) in result.completed {
+                if skills.contains(&skill.as_str()) {
+                    dates.insert(skill, Some(now));
+                }
+            }
+            for skill in result.abandoned {
+                if skills.contains(&skill.as_str()) {
+                    dates.insert(skill, None);
+                }
+            }
+        }
+        now = now.succ_opt().unwrap();
+    }
+    for skill in skills {
+        dates.entry(skill.to_string()).or_insert(None);
+    }
+    dates
+}
+
+// When a skill completes, for report_tradeoff.
+fn completes(skill: &str, date: Option<NaiveDate>) -> String {
+    match date {
+        Some(date) => format!("{} on {}", skill, date),
+        None => format!("{} never completes", skill),
+    }
+}
+
+// Compares finishing one target before the other against interleaving them.
+fn report_tradeoff(
+    persons: &BTreeMap<Name, Person>,
+    now: NaiveDate,
+    name: &str,
+    first: &str,
+    second: &str,
+    options: &Options,
+) {
+    for skill in [first, second] {
+        if !persons[name].target.contains_key(skill) {
+            panic!(
+                "Tradeoff needs an active target: {} has none for {}",
+                name, skill
+            );
+        }
+    }
+    let skills = [first, second];
+    let interleaved = completion_dates(persons.clone(), now, name, &skills, options);
     println!(
-        "Total ROI: {:0.2}, ROI/day: {:0.2}",
-        sum_roi,
-        sum_roi / days as f32
+        "{}: Tradeoff for {} between {} and {}",
+        now, name, first, second
     );
     println!(
-        "Total Wasted Time: {:0.2} hours, Wasted Time/day: {:0.2} hours",
-        sum_wasted_time,
-        sum_wasted_time / days as f32
+        "  Interleaved: {}, {}",
+        completes(first, interleaved[first]),
+        completes(second, interleaved[second])
     );
-    info!("Simulation complete.");
+    for (prioritized, other) in [(first, second), (second, first)] {
+        // Force the prioritized skill to the front by boosting its preference
+        // far above anything else.
+        let mut persons = persons.clone();
+        persons.get_mut(name).unwrap().focus.insert(
+            prioritized.to_string(),
+            Focus {
+                multiplier: TRADEOFF_PRIORITY_MULTIPLIER,
+                until: NaiveDate::MAX,
+            },
+        );
+        let dates = completion_dates(persons, now, name, &skills, options);
+        let change = match (dates[other], interleaved[other]) {
+            (Some(date), Some(before)) => format!(
+                " ({:+} days for {} vs. interleaved)",
+                (date - before).num_days(),
+                other
+            ),
+            _ => String::new(),
+        };
+        println!(
+            "  {} first: {}, {}{}",
+            prioritized,
+            completes(prioritized, dates[prioritized]),
+            completes(other, dates[other]),
+            change
+        );
+    }
 }
 
-fn simulate_day(persons: &mut BTreeMap<&str, Person>, now: NaiveDate) -> (f32, f32) {
-    info!("Date: {}", now);
-    let mut sum_roi = 0.0;
-    let mut sum_wasted_time = 0.0;
-    for (_, person) in persons.iter_mut() {
-        let day = simulate_person(&now, person);
-        sum_roi += day.total_roi;
-        sum_wasted_time += day.wasted_time;
-        for (skill, effective_hours_trained) in day.increment {
-            person.target.get_mut(skill).unwrap().hours_needed -= effective_hours_trained;
-            if person.target[skill].hours_needed <= 0.0 {
-                person
-                    .skills
-                    .insert(skill, person.target[skill].target_rank);
-                person.target.remove(skill);
-                println!(
-                    "{}: {} has reached target rank of {} for {}",
-                    now, person.name, person.skills[skill], skill
-                );
+// Without an upper bound on every ROI variable the objective is unbounded.
+// This looks at the constraints the problem actually has, so that it catches
+// a bound that was never added, whatever the reason.
+fn assert_bounded(problem: &LpProblem, roi: &BTreeMap<Skill, LpContinuous>) {
+    use lp_modeler::dsl::Constraint;
+    use lp_modeler::format::lp_format::LpFileFormat;
+    let bounded: BTreeSet<String> = problem
+        .constraints
+        .iter()
+        .filter(|c| matches!(c.1, Constraint::LessOrEqual | Constraint::Equal))
+        .map(|c| c.0.to_lp_file_format())
+        .collect();
+    for (skill, var) in roi.iter() {
+        assert!(
+            bounded.contains(&var.name),
+            "ROI for {} has no upper bound; the problem would be unbounded",
+            skill
+        );
+    }
+}
+
+// Every pairwise overlap among the skills, each pair once.
+fn pair_overlaps(skills: &[Skill], bonus: f32) -> Vec<Overlap> {
+    let mut pairs = vec![];
+    for (i, a) in skills.iter().enumerate() {
+        for b in skills[i + 1..].iter() {
+            pairs.push(Overlap {
+                combo: vec![a.clone(), b.clone()],
+                bonus,
+                segment_bonus: btreemap! {},
+                min_rank: btreemap! {},
+            });
+        }
+    }
+    pairs
+}
+
+// How much a hypothetical overlap would add to a person's ROI today. One
+// with the same combo as an existing overlap replaces it.
+fn try_overlap(person: &Person, now: NaiveDate, overlap: Overlap, options: &Options) -> f32 {
+    let without = simulate_person(&now, person, options).total_roi;
+    let mut with = person.clone();
+    with.overlap.retain(|o| o.combo != overlap.combo);
+    with.overlap.push(overlap);
+    simulate_person(&now, &with, options).total_roi - without
+}
+
+struct SimulatedDay {
+    total_roi: f32,
+    wasted_time: f32,
+    // Hours actually used from each segment.
+    segment_usage: BTreeMap<Segment, f32>,
+    increment: BTreeMap<Skill, f32>,
+    // Schedule hours spent on each combo that got any time at all.
+    combo_hours: BTreeMap<Vec<Skill>, f32>,
+    // How the solver finished.
+    status: solvers::Status,
+    // Soft constraints broken to find a feasible plan.
+    relaxed: Vec<String>,
+    // The largest relative difference in objective between MiniLP and CBC,
+    // over every solve that went into the plan, with --cross-check.
+    disagreement: f32,
+    // Hours of upkeep done, by skill.
+    maintenance: BTreeMap<Skill, f32>,
+}
+
+// Returns effective training hours for the day.
+fn simulate_person(now: &NaiveDate, person: &Person, options: &Options) -> SimulatedDay {
+    // Degenerate weights would still solve, just to garbage.
+    if let Some(problem) = preference_problems(person, options.allow_negative_preference).first() {
+        panic!("{}", problem);
+    }
+    // Capping the number of skills trained is a MILP; see constraint 16.
+    let limit = options
+        .max_concurrent
+        .or(person.max_concurrent)
+        .filter(|n| *n < person.target.len());
+    // With --auto-relax, an infeasible day is retried with ever fewer soft
+    // constraints, rather than giving up.
+    let relaxations: &[Relaxation] = if options.auto_relax {
+        &[
+            Relaxation::None,
+            Relaxation::SafetyLimits,
+            Relaxation::ScheduleLimits,
+        ]
+    } else {
+        &[Relaxation::None]
+    };
+    let mut disagreement: f32 = 0.0;
+    for relaxation in relaxations {
+        let mut best: Option<(SimulatedDay, f32)> = None;
+        // MiniLP has no integer variables, so the indicators are branched on
+        // here instead. Without a cap this is just the one solve.
+        let mut open = vec![Concurrency {
+            limit,
+            fixed: btreemap! {},
+        }];
+        while let Some(node) = open.pop() {
+            let solved = if options.lexicographic {
+                solve_lexicographic(now, person, options, &node, *relaxation)
+            } else {
+                solve_person(
+                    now,
+                    person,
+                    options,
+                    &node,
+                    *relaxation,
+                    &LexStage::default(),
+                )
+            };
+            let Some((day, objective)) = solved else {
+                continue;
+            };
+            disagreement = disagreement.max(day.disagreement);
+            // A relaxed plan is at least as good as any that could be made
+            // from it by fixing indicators, so one that's no better than the
+            // best so far can be dropped. That doesn't hold for the final
+            // stage of a lexicographic solve.
+            if !options.lexicographic && best.as_ref().is_some_and(|(_, b)| objective <= *b) {
+                continue;
+            }
+            let trained: Vec<Skill> = day
+                .increment
+                .iter()
+                .filter(|(_, hours)| **hours > EPSILON)
+                .map(|(skill, _)| skill.clone())
+                .collect();
+            if limit.is_none_or(|n| trained.len() <= n) {
+                if best.as_ref().is_none_or(|(_, b)| objective > *b) {
+                    best = Some((day, objective));
+                }
+                continue;
+            }
+            // Too many skills got time, so some indicator came out
+            // fractional. Branch on the least trained one that's still free:
+            // first leaving it out, then (tried first) keeping it in.
+            let skill = trained
+                .iter()
+                .filter(|skill| !node.fixed.contains_key(*skill))
+                .min_by(|a, b| day.increment[*a].total_cmp(&day.increment[*b]))
+                .cloned()
+                .expect("Indicators fixed for more skills than the cap allows");
+            for keep in [false, true] {
+                let mut child = node.clone();
+                child.fixed.insert(skill.clone(), keep);
+                open.push(child);
+            }
+        }
+        if let Some((mut day, _)) = best {
+            day.disagreement = disagreement;
+            return day;
+        }
+    }
+    panic!(
+        "Failed to find a training schedule for {} on {}.",
+        person.name, now
+    );
+}
+
+// Nudges preferences into the order given by a tie-break policy. The nudges
+// all add up to less than the priority offset, so they only settle ties and
+// never override an actual preference.
+fn apply_tiebreak(
+    preference: &mut BTreeMap<Skill, f32>,
+    person: &Person,
+    tiebreak: TieBreak,
+    priority_offset: f32,
+) {
+    let mut order: Vec<Skill> = preference.keys().cloned().collect();
+    match tiebreak {
+        TieBreak::None => return,
+        TieBreak::Category => order.sort_by_key(|skill| {
+            [&*ATTRIBUTES, &*ABILITIES, &*PSIONICS]
+                .iter()
+                .position(|category| category.contains(skill.as_str()))
+                .unwrap_or(usize::MAX)
+        }),
+        TieBreak::Cheapest => order.sort_by(|a, b| {
+            person.target[a]
+                .hours_needed
+                .total_cmp(&person.target[b].hours_needed)
+        }),
+    }
+    let step = priority_offset / (order.len() + 1) as f32;
+    for (i, skill) in order.iter().enumerate() {
+        *preference.get_mut(skill).unwrap() += (order.len() - i) as f32 * step;
+    }
+}
+
+// Solves a day in order of preference: each skill in turn gets as much as it
+// can without taking anything from the skills before it. A final solve with
+// the usual objective then settles whatever is left.
+fn solve_lexicographic(
+    now: &NaiveDate,
+    person: &Person,
+    options: &Options,
+    concurrency: &Concurrency,
+    relaxation: Relaxation,
+) -> Option<(SimulatedDay, f32)> {
+    let excluded = concurrency.excluded();
+    let mut order: Vec<Skill> = person
+        .target
+        .keys()
+        .filter(|skill| !excluded.contains(*skill))
+        .cloned()
+        .collect();
+    order.sort_by(|a, b| {
+        person
+            .preference_on(b, *now)
+            .total_cmp(&person.preference_on(a, *now))
+    });
+    let mut stage = LexStage::default();
+    let mut disagreement: f32 = 0.0;
+    for skill in order {
+        stage.maximize = Some(skill.clone());
+        let (day, _) = solve_person(now, person, options, concurrency, relaxation, &stage)?;
+        stage.fixed.insert(skill.clone(), day.increment[&skill]);
+        disagreement = disagreement.max(day.disagreement);
+    }
+    stage.maximize = None;
+    let (mut day, objective) = solve_person(now, person, options, concurrency, relaxation, &stage)?;
+    day.disagreement = day.disagreement.max(disagreement);
+    Some((day, objective))
+}
+
+// The cap on how many targeted skills a solve may train, if any, and the
+// skills whose indicator has been fixed to trained (true) or not (false).
+#[derive(Default, Clone)]
+struct Concurrency {
+    limit: Option<usize>,
+    fixed: BTreeMap<Skill, bool>,
+}
+
+impl Concurrency {
+    fn excluded(&self) -> BTreeSet<Skill> {
+        self.fixed
+            .iter()
+            .filter(|(_, trained)| !**trained)
+            .map(|(skill, _)| skill.clone())
+            .collect()
+    }
+}
+
+// One stage of a lexicographic solve: the ROI already secured for
+// higher-priority skills, and the skill to maximize next, if any.
+#[derive(Default)]
+struct LexStage {
+    fixed: BTreeMap<Skill, f32>,
+    maximize: Option<Skill>,
+}
+
+// Writes out a day's whole LP, the solution, and each constraint's slack, for
+// Task::DebugProblem. Variables are listed by the names lp_name gave them, as
+// in the LP itself and in write_mps, so the three can be compared.
+fn dump_problem(
+    person: &Person,
+    now: &NaiveDate,
+    relaxation: Relaxation,
+    problem: &LpProblem,
+    solution: &Solution,
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    use lp_modeler::format::lp_format::LpFileFormat;
+    writeln!(
+        out,
+        "=== {} on {}, {:?} relaxed: {:?} ===",
+        person.name, now, relaxation, solution.status
+    )?;
+    writeln!(out, "{}", problem.to_lp_file_format())?;
+    writeln!(out, "Values:")?;
+    let values: BTreeMap<&String, &f32> = solution.results.iter().collect();
+    for (name, value) in values {
+        writeln!(out, "  {} = {}", name, value)?;
+    }
+    writeln!(out, "Slack:")?;
+    for constraint in problem.constraints.iter() {
+        // lp-modeler can only evaluate an objective, so evaluate each
+        // constraint as one.
+        let mut probe = LpProblem::new("probe", LpObjective::Maximize);
+        let mut difference = constraint.0.clone();
+        difference -= constraint.2.clone();
+        probe += difference;
+        let value =
+            Solution::with_problem(solution.status.clone(), solution.results.clone(), &probe)
+                .eval()
+                .unwrap_or(0.0);
+        let slack = match constraint.1 {
+            Constraint::LessOrEqual => -value,
+            Constraint::GreaterOrEqual => value,
+            Constraint::Equal => -value.abs(),
+        };
+        writeln!(out, "  {}: {}", constraint.to_lp_file_format(), slack)?;
+    }
+    Ok(())
+}
+
+// Writes a problem in free MPS format under the given name, with the same
+// row and variable names as its LP format. Free MPS separates fields with
+// spaces, so the problem's name goes through lp_name like its variables'. lp-modeler has no MPS writer, and
+// keeps expressions private, so each coefficient is found by evaluating rows
+// at unit vectors.
+fn write_mps(
+    problem: &LpProblem,
+    name: &str,
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    use lp_modeler::dsl::Constraint;
+    use std::collections::HashMap;
+    // An objective loses its constant term, unless it's multiplied by a
+    // variable that's then set to one.
+    let one = LpContinuous::new("one");
+    let evaluate = |expression: &LpExpression, mut values: HashMap<String, f32>| {
+        let mut probe = LpProblem::new("probe", LpObjective::Maximize);
+        probe += expression.clone() * &one;
+        values.insert(one.name.clone(), 1.0);
+        Solution::with_problem(solvers::Status::Optimal, values, &probe)
+            .eval()
+            .unwrap_or(0.0)
+    };
+    let mut variables: Vec<String> = problem.variables().into_keys().collect();
+    variables.sort();
+    // Each row as an expression that's compared to zero.
+    let mut rows: Vec<(String, &str, LpExpression)> = vec![];
+    if let Some(objective) = problem.obj_expr_arena.as_ref() {
+        rows.push(("obj".to_string(), "N", objective.clone()));
+    }
+    for (i, constraint) in problem.constraints.iter().enumerate() {
+        let mut difference = constraint.0.clone();
+        difference -= constraint.2.clone();
+        let kind = match constraint.1 {
+            Constraint::LessOrEqual => "L",
+            Constraint::GreaterOrEqual => "G",
+            Constraint::Equal => "E",
+        };
+        rows.push((format!("c{}", i + 1), kind, difference));
+    }
+    let constants: Vec<f32> = rows
+        .iter()
+        .map(|(_, _, row)| evaluate(row, HashMap::new()))
+        .collect();
+    writeln!(out, "NAME {}", lp_name(name))?;
+    if let LpObjective::Maximize = problem.objective_type {
+        writeln!(out, "OBJSENSE\n    MAX")?;
+    }
+    writeln!(out, "ROWS")?;
+    for (name, kind, _) in rows.iter() {
+        writeln!(out, " {} {}", kind, name)?;
+    }
+    writeln!(out, "COLUMNS")?;
+    for variable in variables.iter() {
+        for ((name, _, row), constant) in rows.iter().zip(constants.iter()) {
+            let coefficient = evaluate(row, HashMap::from([(variable.clone(), 1.0)])) - constant;
+            if coefficient != 0.0 {
+                writeln!(out, "    {} {} {}", variable, name, coefficient)?;
             }
         }
     }
-    (sum_roi, sum_wasted_time)
+    writeln!(out, "RHS")?;
+    for ((name, kind, _), constant) in rows.iter().zip(constants.iter()) {
+        if *kind != "N" && *constant != 0.0 {
+            writeln!(out, "    RHS {} {}", name, -constant)?;
+        }
+    }
+    // As in the LP format, variables have no bounds unless constrained.
+    writeln!(out, "BOUNDS")?;
+    for variable in variables.iter() {
+        writeln!(out, " FR BND {}", variable)?;
+    }
+    writeln!(out, "ENDATA")
 }
 
-struct SimulatedDay {
-    total_roi: f32,
-    wasted_time: f32,
-    increment: BTreeMap<Skill, f32>,
+// The name of a variable in solve_person's problems. Skill and segment names
+// can hold spaces, which would split a name in two in the LP and MPS formats,
+// so anything but letters, digits and underscores becomes an underscore.
+fn lp_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
 }
 
-// Returns effective training hours for the day.
-fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
+// Soft constraints that may be dropped to find a feasible plan, cumulatively.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Relaxation {
+    None,
+    SafetyLimits,
+    ScheduleLimits,
+}
+
+// Solves one day's training plan, leaving out the excluded skills, and
+// training no more than the concurrency cap allows.
+// Returns the plan along with its objective value.
+fn solve_person(
+    now: &NaiveDate,
+    person: &Person,
+    options: &Options,
+    concurrency: &Concurrency,
+    relaxation: Relaxation,
+    stage: &LexStage,
+) -> Option<(SimulatedDay, f32)> {
+    let excluded = &concurrency.excluded();
+    // Segments with no time in them can't contribute anything, so leave them
+    // out of the model entirely.
+    let scale = person.time_scale_on(*now) / options.steps_per_day as f32;
+    let mut schedule: BTreeMap<Segment, f32> = person
+        .schedule_on(*now)
+        .iter()
+        .filter(|(_, duration)| **duration * scale > 0.0)
+        .map(|(seg, duration)| (seg.clone(), *duration * scale))
+        .collect();
+    // Time banked on sabbatical is an extra segment, open to any skill.
+    if let Some(banked) = person.banked_on(*now) {
+        schedule.insert(BANKED_SEGMENT.to_string(), banked);
+    }
+
     // Define problem variables.
     //
     // Total return on investment, aka. skill-up points -- one per skill.
@@ -226,7 +2551,7 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
     let mut roi: BTreeMap<Skill, LpContinuous> = btreemap! {};
     for skill in person.target.keys() {
         let name = format!("ROI_{}", skill);
-        roi.insert(skill, LpContinuous::new(&name));
+        roi.insert(skill.clone(), LpContinuous::new(&lp_name(&name)));
     }
 
     // The time spent on each skill, by skill.
@@ -234,51 +2559,191 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
     let mut invested_skill: BTreeMap<Skill, LpContinuous> = btreemap! {};
     for skill in person.target.keys() {
         let name = format!("skill_{}", skill);
-        invested_skill.insert(skill, LpContinuous::new(&name));
+        invested_skill.insert(skill.clone(), LpContinuous::new(&lp_name(&name)));
     }
 
     // The time spent in each segment, by segment.
     let mut invested_seg: BTreeMap<Segment, LpContinuous> = btreemap! {};
-    for seg in person.schedule.keys() {
+    for seg in schedule.keys() {
         let name = format!("segment_{}", seg);
-        invested_seg.insert(seg, LpContinuous::new(&name));
+        invested_seg.insert(seg.clone(), LpContinuous::new(&lp_name(&name)));
     }
 
     // The time spent on each skill *combo*, by segment and combo.
     // This is needed to calculate the overlap bonus, and is the primary
     // thing you can think of the solver as optimizing.
+    // Combos without a targeted skill could only ever waste time, so they
+    // are left out; most of a person's trivial overlaps are like that.
     let mut invested_seg_combo: BTreeMap<(Segment, Vec<Skill>), LpContinuous> = btreemap! {};
-    for seg in person.schedule.keys() {
-        for combo in person.overlap.iter() {
-            let name = format!("combo_{}_{}", seg, combo.combo.join("_"));
-            invested_seg_combo.insert((seg, combo.combo.clone()), LpContinuous::new(&name));
+    for seg in schedule.keys() {
+        for combo in person.overlap.iter().filter(|o| {
+            o.combo
+                .iter()
+                .any(|skill| person.target.contains_key(skill))
+        }) {
+            let name = format!("combo_{}_{}", seg, combo_name(&combo.combo));
+            invested_seg_combo.insert(
+                (seg.clone(), combo.combo.clone()),
+                LpContinuous::new(&lp_name(&name)),
+            );
+        }
+    }
+
+    // The time spent on upkeep, by segment and skill. Upkeep trains nothing,
+    // but keeps the skill from decaying.
+    let mut maintained: BTreeMap<(Segment, Skill), LpContinuous> = btreemap! {};
+    for seg in schedule.keys() {
+        for skill in person.maintenance.keys() {
+            let name = format!("maintain_{}_{}", seg, skill);
+            maintained.insert(
+                (seg.clone(), skill.clone()),
+                LpContinuous::new(&lp_name(&name)),
+            );
         }
     }
 
+    // Someone with only upkeep to do and no time to do it in has nothing to
+    // plan, and the solver would have no objective to maximize.
+    if roi.is_empty() && maintained.is_empty() {
+        let day = SimulatedDay {
+            total_roi: 0.0,
+            wasted_time: 0.0,
+            segment_usage: btreemap! {},
+            increment: btreemap! {},
+            combo_hours: btreemap! {},
+            status: solvers::Status::Optimal,
+            relaxed: vec![],
+            disagreement: 0.0,
+            maintenance: btreemap! {},
+        };
+        return Some((day, 0.0));
+    }
+
     // Define objective function: maximize the total return on investment.
-    let mut problem = LpProblem::new(person.name, LpObjective::Maximize);
-    for (skill, var) in roi.iter() {
-        problem += var * person.preference[skill];
+    // lp-modeler only takes a static name, so the person goes by name only
+    // in dumped MPS files.
+    let mut problem = LpProblem::new("training", LpObjective::Maximize);
+    let mut preference: BTreeMap<Skill, f32> = roi
+        .keys()
+        .map(|skill| (skill.clone(), person.preference_on(skill, *now)))
+        .collect();
+    apply_tiebreak(
+        &mut preference,
+        person,
+        options.tiebreak,
+        options.priority_offset,
+    );
+    // What an hour of ROI in each skill is worth to the ordinary objective.
+    let most_needed = person
+        .target
+        .values()
+        .map(|target| target.hours_needed)
+        .fold(EPSILON, f32::max);
+    let weight = |skill: &Skill| {
+        if options.balanced {
+            // Weighting by remaining hours favours whichever target is
+            // furthest from done, so that all targets advance together.
+            // (Dividing instead would finish the nearly-done ones first.)
+            // They're taken relative to the most any target needs, so no
+            // weight is above the skill's preference, and upkeep still comes
+            // first however far off a target is.
+            preference[skill] * person.target[skill].hours_needed / most_needed
+        } else {
+            preference[skill]
+        }
+    };
+    if let Some(skill) = stage.maximize.as_ref() {
+        // A lexicographic stage cares about nothing but its one skill.
+        problem += &roi[skill] * 1.0;
+    } else {
+        for (skill, var) in roi.iter() {
+            problem += var * weight(skill);
+        }
+    }
+    for var in maintained.values() {
+        problem += var * MAINTENANCE_WEIGHT;
+    }
+    // Skills with a negative preference are penalized, so the solver would
+    // rather leave time idle than train them. Reward any time spent on a
+    // targeted skill just enough to outweigh that penalty, as weighted in the
+    // objective, so they are still trained as a last resort once nothing else
+    // can use the time.
+    let max_penalty = roi
+        .keys()
+        .map(weight)
+        .filter(|w| *w < 0.0)
+        .map(|w| -w)
+        .fold(0.0, f32::max);
+    if max_penalty > 0.0 && stage.maximize.is_none() {
+        let max_bonus = person
+            .overlap
+            .iter()
+            .flat_map(|o| {
+                let boost = person.boost_on(&o.combo, *now);
+                std::iter::once(o.bonus)
+                    .chain(o.segment_bonus.values().cloned())
+                    .map(move |bonus| bonus * boost)
+            })
+            .fold(1.0, f32::max)
+            * person.quality.values().cloned().fold(1.0, f32::max)
+            * person.limit_focus.values().cloned().fold(1.0, f32::max);
+        let fill_weight = max_penalty * max_bonus + 1.0;
+        for var in invested_skill.values() {
+            problem += var * fill_weight;
+        }
+    }
+
+    // With --spill, ROI beyond what a skill needs goes on to the next skill
+    // down in the objective, instead of the time being left idle. Spilling
+    // only ever loses value, so the solver keeps ROI where it was earned
+    // until that skill's target is met. That doesn't hold when the objective
+    // is a single skill or the slowest one, so those don't spill.
+    let mut spill: BTreeMap<Skill, (LpContinuous, Skill)> = btreemap! {};
+    if options.spill && stage.maximize.is_none() && !options.maxmin {
+        let mut order: Vec<Skill> = roi.keys().cloned().collect();
+        order.sort_by(|a, b| weight(b).total_cmp(&weight(a)));
+        for pair in order.windows(2) {
+            let var = LpContinuous::new(&lp_name(&format!("spill_{}", pair[0])));
+            problem += &var * -SPILL_COST;
+            spill.insert(pair[0].clone(), (var, pair[1].clone()));
+        }
     }
 
+    // Focus multipliers go with the schedule limits they belong to.
+    let focus_in = |seg: &str| {
+        if relaxation >= Relaxation::ScheduleLimits {
+            1.0
+        } else {
+            person.limit_focus_in(seg)
+        }
+    };
+
     // Define constraints.
     // 1. Spent time cannot be negative, for any segment/combo or skill.
+    //    Nor can ROI, or spilled ROI.
     for var in invested_skill
         .values()
+        .chain(roi.values())
         .chain(invested_seg.values())
         .chain(invested_seg_combo.values())
+        .chain(spill.values().map(|(var, _)| var))
+        .chain(maintained.values())
     {
         problem += constraint!(var >= 0.0);
     }
     // 2. Time spent from a segment must be less than the segment limit.
-    for (seg, limit) in person.schedule.iter() {
+    for (seg, limit) in schedule.iter() {
         let var = invested_seg.get(seg).unwrap();
         problem += constraint!(var <= limit);
     }
     // 3. Time spent on a skill must be less than the skill's safety limit, if any.
+    //    Daily limits are shared out evenly between the steps of a day.
+    let steps = options.steps_per_day as f32;
     for (skill, limit) in person.safety_limit.iter() {
         if let Some(var) = invested_skill.get(skill) {
-            problem += constraint!(var <= limit);
+            if relaxation < Relaxation::SafetyLimits {
+                problem += constraint!(var <= limit / steps);
+            }
         }
     }
     // 4. Time spent on a skill equals the sum of time spent on each combo that includes it.
@@ -294,7 +2759,7 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
         problem += antisum.equal(0.0);
     }
     // 5. Time spent in a segment equals the sum of time spent on each combo in it...
-    //    multiplied by the size of the combo.
+    //    multiplied by the size of the combo, plus any upkeep done in it.
     for (seg, total) in invested_seg.iter() {
         // Same trick as above.
         let mut antisum = LpExpression::from(total);
@@ -303,14 +2768,29 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
                 antisum -= var * combo.len() as f32;
             }
         }
+        for ((m_seg, _), var) in maintained.iter() {
+            if m_seg == seg {
+                antisum -= var;
+            }
+        }
         problem += antisum.equal(0.0);
     }
     // 6. Return on investment equals the sum of time spent on each combo that includes it,
-    //    multiplied by the bonus for that combo.
+    //    multiplied by the bonus for that combo, any boost to it, and the quality of
+    //    training in its segment, and its focus if the segment is limited.
+    //    With --spill, less what it spills on, plus what spills into it.
     for (skill, total) in roi.iter() {
         // Same trick as above.
         let mut antisum = LpExpression::from(total);
-        for ((_, combo), var) in invested_seg_combo.iter() {
+        for (from, (var, into)) in spill.iter() {
+            if from == skill {
+                antisum += var;
+            }
+            if into == skill {
+                antisum -= var;
+            }
+        }
+        for ((seg, combo), var) in invested_seg_combo.iter() {
             if combo.contains(skill) {
                 // Yeah yeah, this is a bit inefficient, but it's not a big deal.
                 let bonus = person
@@ -318,8 +2798,9 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
                     .iter()
                     .find(|o| o.combo == *combo)
                     .unwrap()
-                    .bonus;
-                antisum -= var * bonus;
+                    .bonus_for(seg, &person.skills)
+                    * person.boost_on(combo, *now);
+                antisum -= var * (bonus * person.quality_in(seg) * focus_in(seg));
             }
         }
         problem += antisum.equal(0.0);
@@ -328,6 +2809,9 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
     //   the time spent on every combo must be zero EXCEPT if it only contains
     //   permitted skills.
     for (seg, allowed) in person.schedule_limit.iter() {
+        if relaxation >= Relaxation::ScheduleLimits {
+            break;
+        }
         debug!(
             "Checking segment {:?} with allowed skills {:?}",
             seg, allowed
@@ -348,85 +2832,340 @@ fn simulate_person(_now: &NaiveDate, person: &Person) -> SimulatedDay {
     for (skill, target) in person.target.iter() {
         problem += constraint!(roi[skill] <= target.hours_needed);
     }
+    assert_bounded(&problem, &roi);
+
+    // 9. Pinned allocations are fixed, and the rest is optimized around them.
+    //    Pins only apply while they train something, and never force more
+    //    training than a target still needs.
+    let mut pinned: BTreeMap<Segment, f32> = btreemap! {};
+    for ((seg, combo), hours) in person.pin.iter() {
+        if !combo.iter().any(|skill| person.target.contains_key(skill)) {
+            continue;
+        }
+        let Some(overlap) = person.overlap.iter().find(|o| o.combo == *combo) else {
+            panic!("Pinned combo {:?} is not a defined overlap", combo);
+        };
+        let bonus = overlap.bonus_for(seg, &person.skills) * person.boost_on(combo, *now);
+        let hours = combo
+            .iter()
+            .filter_map(|skill| person.target.get(skill))
+            .map(|target| target.hours_needed / (bonus * person.quality_in(seg) * focus_in(seg)))
+            .fold(*hours / steps, f32::min);
+        let Some(var) = invested_seg_combo.get(&(seg.clone(), overlap.combo.clone())) else {
+            panic!(
+                "Pinned segment {} is not in {}'s schedule",
+                seg, person.name
+            );
+        };
+        *pinned.entry(seg.clone()).or_insert(0.0) += hours * combo.len() as f32;
+        if pinned[seg] > schedule[seg] + EPSILON {
+            panic!(
+                "Pins in segment {} need {} hours, but it only has {}",
+                seg, pinned[seg], schedule[seg]
+            );
+        }
+        problem += var.equal(hours);
+    }
+
+    // 10. Skills left out of today's plan get no time at all.
+    for skill in excluded.iter() {
+        problem += invested_skill[skill].equal(0.0);
+    }
+    // 11. Total training can't exceed what's left of the lifetime budget.
+    if let Some(budget) = person.lifetime_budget {
+        let vars: Vec<LpContinuous> = roi.values().cloned().collect();
+        problem += lp_sum(&vars).le(budget.max(0.0));
+    }
+    // 12. In max-min mode, the slowest skill's progress, as a fraction of what
+    //     it still needs, is what counts. Maximizing it leaves ties, which the
+    //     ordinary objective settles so that leftover time isn't wasted.
+    if options.maxmin {
+        let min_progress = LpContinuous::new("min_progress");
+        for (skill, target) in person.target.iter() {
+            if !excluded.contains(skill) {
+                let mut progress = LpExpression::from(&roi[skill]);
+                progress -= &min_progress * target.hours_needed;
+                problem += progress.ge(0.0);
+            }
+        }
+        if stage.maximize.is_none() {
+            problem += &min_progress * MAXMIN_WEIGHT;
+        }
+    }
+    // 13. Custom linear constraints over time spent per skill. These only
+    //     apply while all of their skills are being trained; once one is
+    //     done, a ratio between them would just stall the rest.
+    for constraint in person.constraints.iter() {
+        if !constraint
+            .terms
+            .iter()
+            .all(|(skill, _)| invested_skill.contains_key(skill) && !excluded.contains(skill))
+        {
+            continue;
+        }
+        let mut lhs = LpExpression::from(0.0);
+        for (skill, coefficient) in constraint.terms.iter() {
+            lhs += &invested_skill[skill] * *coefficient;
+        }
+        problem += match constraint.op {
+            Comparison::AtMost => lhs.le(constraint.rhs),
+            Comparison::AtLeast => lhs.ge(constraint.rhs),
+            Comparison::Equal => lhs.equal(constraint.rhs),
+        };
+    }
+    // 14. Skills already settled by earlier lexicographic stages keep what
+    //     they got. A little slack keeps rounding from making this infeasible.
+    for (skill, floor) in stage.fixed.iter() {
+        problem += constraint!(roi[skill] >= (floor - EPSILON));
+    }
+
+    // 15. Upkeep is done up to what's due today, shared out between the steps
+    //     of the day, and only in segments where the skill may be trained.
+    //     A day with no time has nothing to constrain.
+    for (skill, maintenance) in person.maintenance.iter() {
+        if schedule.is_empty() {
+            break;
+        }
+        let mut sum = LpExpression::from(0.0);
+        for ((seg, m_skill), var) in maintained.iter() {
+            if m_skill != skill {
+                continue;
+            }
+            sum += var;
+            let allowed = person.schedule_limit.get(seg);
+            if relaxation < Relaxation::ScheduleLimits
+                && allowed.is_some_and(|allowed| !allowed.contains(skill))
+            {
+                problem += var.equal(0.0);
+            }
+        }
+        problem += sum.le(maintenance.due_today() / steps);
+    }
+
+    // 16. With a cap on how many skills are trained at once, each targeted
+    //     skill has an indicator of whether it gets any time at all, and no
+    //     more than the cap may be set. These should be binary, but MiniLP
+    //     can't do that, so they run from 0 to 1 here and simulate_person
+    //     branches on them; those it has fixed are fixed here.
+    if let Some(n) = concurrency.limit {
+        let most: f32 = schedule.values().sum();
+        let mut sum = LpExpression::from(0.0);
+        for (skill, var) in invested_skill.iter() {
+            let indicator = LpContinuous::new(&lp_name(&format!("trained_{}", skill)));
+            problem += constraint!(indicator >= 0.0);
+            problem += constraint!(indicator <= 1.0);
+            match concurrency.fixed.get(skill) {
+                Some(true) => problem += indicator.equal(1.0),
+                Some(false) => problem += indicator.equal(0.0),
+                None => {}
+            }
+            let mut time = LpExpression::from(var);
+            time -= &indicator * most;
+            problem += time.le(0.0);
+            sum += &indicator;
+        }
+        if !invested_skill.is_empty() {
+            problem += sum.le(n as f32);
+        }
+    }
 
     // Solve the problem.
-    let solver = solvers::MiniLpSolver::new();
-    let solution = solver
-        .run(&problem)
-        .expect("Failed to find a training schedule.");
+    // lp-modeler's MiniLP solver starts each solve from scratch, and CBC, as
+    // used by --cross-check, is a separate program that reads each problem
+    // from a file, so neither can warm-start. With --warm-start, MiniLP is
+    // used directly instead; see warm.rs. On a four-year run (the example
+    // with every target at rank 9) that cut time spent solving from 0.47s
+    // to 0.16s.
+    let solution = if options.warm_start {
+        warm::solve(&person.name, &problem)
+    } else {
+        solvers::MiniLpSolver::new()
+            .run(&problem)
+            .expect("Failed to find a training schedule.")
+    };
+    if person.debug_problem.contains(now) {
+        let mut out = std::io::stdout();
+        dump_problem(person, now, relaxation, &problem, &solution, &mut out)
+            .expect("Failed to dump the problem");
+        if let Some(dir) = options.dump_mps.as_ref() {
+            // There can be several solves per day, so number the files.
+            static DUMPED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            let n = DUMPED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let path = format!("{}/{}-{}-{}.mps", dir, person.name, now, n);
+            let mut file = std::fs::File::create(&path).expect("Failed to create MPS file");
+            write_mps(&problem, &person.name, &mut file).expect("Failed to write MPS file");
+        }
+    }
+    if !matches!(
+        solution.status,
+        solvers::Status::Optimal | solvers::Status::SubOptimal
+    ) {
+        debug!(
+            "No plan for {} on {} with {:?} relaxed: {:?}",
+            person.name, now, relaxation, solution.status
+        );
+        return None;
+    }
     debug!("Solution: {:?}", solution);
-
-    // problem.write_lp("/dev/stdout").unwrap();
-
-    // // Print the results...
-    // println!("Total RoI:");
-    // let mut total = 0.0;
-    // for (skill, var) in roi.iter() {
-    //     println!("  {}: {}", skill, solution.get_float(var));
-    //     total += solution.get_float(var);
-    // }
-    // println!("  Total: {}", total);
-    // println!("Time spent on skills:");
-    // for (skill, var) in invested_skill.iter() {
-    //     println!("  {}: {}", skill, solution.get_float(var));
-    // }
-    // println!("Time spent on segments:");
-    // for (seg, var) in invested_seg.iter() {
-    //     println!("  {}: {}", seg, solution.get_float(var));
-    // }
-    // println!("Time spent on combos:");
-    // for ((seg, combo), var) in invested_seg_combo.iter() {
-    //     let value = solution.get_float(var);
-    //     if value != 0.0 {
-    //         println!("  {} {}: {}", seg, combo.join("_"), value);
-    //     }
-    // }
+    debug!(
+        "Solver status for {} on {}: {:?}",
+        person.name, now, solution.status
+    );
+    let objective =
+        Solution::with_problem(solution.status.clone(), solution.results.clone(), &problem)
+            .eval()
+            .unwrap();
+    // With --cross-check, CBC solves the same problem to compare against. A
+    // plan it can't find at all is as far off as it gets.
+    let mut disagreement = 0.0;
+    if options.cross_check {
+        let checked = solvers::CbcSolver::new()
+            .run(&problem)
+            .unwrap_or_else(|e| panic!("--cross-check needs CBC installed as cbc: {}", e));
+        disagreement = match checked.status {
+            solvers::Status::Optimal => {
+                let other = Solution::with_problem(checked.status, checked.results, &problem)
+                    .eval()
+                    .unwrap();
+                (objective - other).abs() / objective.abs().max(1.0)
+            }
+            _ => f32::INFINITY,
+        };
+    }
 
     // Check for wasted time.
     let mut wasted_time = 0.0;
-    for (seg, limit) in person.schedule.iter() {
+    let mut segment_usage = BTreeMap::new();
+    for (seg, limit) in schedule.iter() {
         let var = invested_seg.get(seg).unwrap();
         let value = solution.get_float(var);
-        if value < *limit {
+        if limit - value > EPSILON && *seg != BANKED_SEGMENT {
             wasted_time += limit - value;
         }
+        segment_usage.insert(seg.clone(), value);
     }
     // Return the results.
+    let mut maintenance = BTreeMap::new();
+    for ((_, skill), var) in maintained.iter() {
+        *maintenance.entry(skill.clone()).or_insert(0.0) += solution.get_float(var);
+    }
     let mut increment = BTreeMap::new();
     let mut total_roi = 0.0;
     for (skill, var) in roi.iter() {
-        increment.insert(*skill, solution.get_float(var));
+        increment.insert(skill.clone(), solution.get_float(var));
         total_roi += solution.get_float(var);
     }
-    SimulatedDay {
+    // Note which soft constraints the plan actually had to break.
+    let mut relaxed = vec![];
+    if relaxation >= Relaxation::SafetyLimits {
+        for (skill, limit) in person.safety_limit.iter() {
+            if let Some(var) = invested_skill.get(skill) {
+                let over = solution.get_float(var) - limit / steps;
+                if over > EPSILON {
+                    relaxed.push(format!(
+                        "exceed the {} safety limit by {:.*}h",
+                        skill, options.precision, over
+                    ));
+                }
+            }
+        }
+    }
+    if relaxation >= Relaxation::ScheduleLimits {
+        for (seg, allowed) in person.schedule_limit.iter() {
+            for ((c_seg, combo), var) in invested_seg_combo.iter() {
+                if c_seg == seg
+                    && !combo.iter().all(|skill| allowed.contains(skill))
+                    && solution.get_float(var) > EPSILON
+                {
+                    relaxed.push(format!("train {} during {}", combo.join("+"), seg));
+                }
+            }
+        }
+    }
+    // A combo takes up time in its segment for each of its skills; see 5.
+    let mut combo_hours = BTreeMap::new();
+    for ((_, combo), var) in invested_seg_combo.iter() {
+        let value = solution.get_float(var);
+        if value > EPSILON {
+            *combo_hours.entry(combo.clone()).or_insert(0.0) += value * combo.len() as f32;
+        }
+    }
+    let day = SimulatedDay {
         increment,
         total_roi,
         wasted_time,
-    }
+        segment_usage,
+        combo_hours,
+        status: solution.status.clone(),
+        relaxed,
+        disagreement,
+        maintenance,
+    };
+    Some((day, objective))
 }
 
 // Computes the number of effective training hours needed to reach a target rank.
-fn effective_training_hours_needed(skill: &str, current_rank: f32, target_rank: f32) -> f32 {
-    const HOURS_PER_WEEK: f32 = 48.0;
-    const WEEKS_PER_MONTH: f32 = 4.0;
-    let increment = target_rank - current_rank;
-    // Costs increase abruptly at each rank, so we can't just use a linear formula
-    // and we can't (currently) handle a target rank that crosses an integer boundary.
-    // 1.5 to 2.0 is fine, 1.0 to 2.0 is fine, but 1.5 to 2.5 is not.
-    let base_rank = current_rank.floor();
-    let target_base_rank = target_rank.floor();
-    if target_base_rank == base_rank {
-        // Always fine.
-    } else if target_base_rank == base_rank + 1.0 && target_rank.fract() == 0.0 {
-        // Also fine.
-    } else {
-        panic!(
-            "Can't handle target rank {} from current rank {}",
-            target_rank, current_rank
-        );
+fn effective_training_hours_needed(
+    skill: &str,
+    bands: Bands,
+    current_rank: f32,
+    target_rank: f32,
+) -> f32 {
+    // Costs increase abruptly at each rank, so we can't just use a linear formula.
+    // Instead each rank band up to the target is paid for at its own cost.
+    let mut hours = 0.0;
+    let mut rank = current_rank;
+    while rank < target_rank {
+        let band_end = bands.next(rank, target_rank);
+        hours += bands.cost(skill, rank) * (band_end - rank);
+        rank = band_end;
+    }
+    hours
+}
+
+// How a skill's ranks are split into bands for costing: the size of each
+// band, and the highest rank held before, below which ranks come back cheaper.
+#[derive(Debug, Clone, Copy)]
+struct Bands {
+    step: f32,
+    peak: f32,
+}
+
+impl Bands {
+    fn of(person: &Person, skill: &str) -> Self {
+        Bands {
+            step: person.rank_step_of(skill),
+            peak: person.peak.get(skill).copied().unwrap_or(0.0),
+        }
+    }
+
+    // Where the band containing a rank ends, or the target if that's sooner.
+    // The old peak ends a band too, since the discount stops there.
+    fn next(&self, rank: f32, target_rank: f32) -> f32 {
+        let end = (((rank / self.step).floor() + 1.0) * self.step).min(target_rank);
+        if rank < self.peak && self.peak < end {
+            self.peak
+        } else {
+            end
+        }
+    }
+
+    // The effective hours per rank for the band starting at a rank.
+    fn cost(&self, skill: &str, rank: f32) -> f32 {
+        let discount = if rank < self.peak {
+            RELEARN_DISCOUNT
+        } else {
+            1.0
+        };
+        hours_per_rank(skill, rank) * discount
     }
+}
 
-    let cost = if base_rank <= 0.0 {
+// The effective training hours one full rank costs, starting from the current rank.
+fn hours_per_rank(skill: &str, current_rank: f32) -> f32 {
+    const HOURS_PER_WEEK: f32 = 48.0;
+    const WEEKS_PER_MONTH: f32 = 4.0;
+    if current_rank.floor() <= 0.0 {
         if ATTRIBUTES.contains(skill) {
             3.0 * HOURS_PER_WEEK * WEEKS_PER_MONTH
         } else if ABILITIES.contains(skill) {
@@ -439,13 +3178,816 @@ fn effective_training_hours_needed(skill: &str, current_rank: f32, target_rank:
     } else {
         if ATTRIBUTES.contains(skill) {
             current_rank * HOURS_PER_WEEK * WEEKS_PER_MONTH
-        } else if ABILITIES.contains(skill) {
-            current_rank * HOURS_PER_WEEK
-        } else if PSIONICS.contains(skill) {
+        } else if ABILITIES.contains(skill) || PSIONICS.contains(skill) {
             current_rank * HOURS_PER_WEEK
         } else {
             panic!("Unknown skill type: {}", skill);
         }
-    };
-    return cost * increment;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet() -> Options {
+        Options {
+            quiet: true,
+            ..Options::default()
+        }
+    }
+
+    fn start() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2009, 10, 17).unwrap()
+    }
+
+    // Ranks, hours or the like, keyed by skill or segment.
+    fn map(entries: &[(&str, f32)]) -> BTreeMap<String, f32> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), *value))
+            .collect()
+    }
+
+    // Someone with the given ranks and schedule, and only trivial overlaps.
+    fn person(name: &str, skills: &[(&str, f32)], segment: &[(&str, f32)]) -> Vec<Task> {
+        vec![
+            Task::Baseline {
+                name: name.into(),
+                template: None,
+                skills: map(skills),
+            },
+            Task::Schedule {
+                name: name.into(),
+                segment: map(segment),
+            },
+            Task::Overlap {
+                name: name.into(),
+                when: vec![],
+            },
+        ]
+    }
+
+    // Simulates until no-one has targets left, and returns how many days
+    // that took.
+    fn days_to_finish(persons: &mut BTreeMap<Name, Person>, options: &Options) -> usize {
+        let mut now = start();
+        let mut days = 0;
+        while persons.values().any(|person| !person.target.is_empty()) {
+            assert!(days < 1000, "Targets still unmet after {} days", days);
+            simulate_day(persons, now, options);
+            now = now.succ_opt().unwrap();
+            days += 1;
+        }
+        days
+    }
+
+    #[test]
+    fn targets_can_start_a_new_skill_from_rank_zero() {
+        let mut person = Person::new("Amu".into(), map(&[("Lore", 1.0)]), DEFAULT_PRIORITY_OFFSET);
+        person.schedule = map(&[("Afternoon", 8.0)]);
+        let target = new_target(&mut person, "Athletics", 1.0);
+        // Rank 0 costs three weeks for an ability.
+        assert_eq!(target.hours_needed, 3.0 * 48.0);
+        person.target.insert("Athletics".into(), target);
+        let mut persons = btreemap! { "Amu".to_string() => person };
+        assert_eq!(days_to_finish(&mut persons, &Options::default()), 18);
+        assert_eq!(persons["Amu"].skills["Athletics"], 1.0);
+    }
+
+    #[test]
+    fn pair_adds_every_pair_once() {
+        let skills = ["Dreamwalking", "Illusion", "Integrity", "Lore"].map(String::from);
+        let pairs = pair_overlaps(&skills, 1.2);
+        assert_eq!(pairs.len(), 6);
+        let combos: BTreeSet<_> = pairs.iter().map(|o| o.combo.clone()).collect();
+        assert_eq!(combos.len(), 6);
+        assert!(combos.iter().all(|combo| combo.len() == 2));
+    }
+
+    #[test]
+    fn bounded_roi_passes() {
+        let roi = btreemap! { "Lore".to_string() => LpContinuous::new("ROI_Lore") };
+        let mut problem = LpProblem::new("test", LpObjective::Maximize);
+        problem += &roi["Lore"] * 1.0;
+        problem += constraint!(roi["Lore"] <= 10.0);
+        assert_bounded(&problem, &roi);
+    }
+
+    #[test]
+    #[should_panic(expected = "ROI for Lore has no upper bound")]
+    fn missing_roi_bound_is_caught() {
+        let roi = btreemap! { "Lore".to_string() => LpContinuous::new("ROI_Lore") };
+        let mut problem = LpProblem::new("test", LpObjective::Maximize);
+        problem += &roi["Lore"] * 1.0;
+        // A lower bound doesn't count.
+        problem += constraint!(roi["Lore"] >= 0.0);
+        assert_bounded(&problem, &roi);
+    }
+
+    #[test]
+    fn negative_preferences_still_use_spare_time() {
+        let skills = map(&[("Illusion", 1.0), ("Lore", 1.0)]);
+        let mut person = Person::new("Amu".into(), skills, DEFAULT_PRIORITY_OFFSET);
+        person.schedule = map(&[("Afternoon", 2.0)]);
+        // Lore can only use half of each day; Illusion is the only use for
+        // the rest.
+        person.safety_limit = map(&[("Lore", 1.0)]);
+        person.preference.insert("Illusion".into(), -1.0);
+        for skill in ["Illusion", "Lore"] {
+            person.overlap.push(Overlap {
+                combo: vec![skill.into()],
+                bonus: 1.0,
+                segment_bonus: btreemap! {},
+                min_rank: btreemap! {},
+            });
+            let target = new_target(&mut person, skill, 2.0);
+            person.target.insert(skill.into(), target);
+        }
+        let mut persons = btreemap! { "Amu".to_string() => person };
+        let options = Options {
+            allow_negative_preference: true,
+            ..Options::default()
+        };
+        assert_eq!(days_to_finish(&mut persons, &options), 48);
+        assert_eq!(persons["Amu"].skills["Illusion"], 2.0);
+    }
+
+    #[test]
+    fn zero_duration_segments_add_nothing() {
+        let plan = |segments: &[(&str, f32)]| {
+            let mut schedule = person("Amu", &[("Lore", 1.0)], segments);
+            schedule.push(Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Lore", 2.0)]),
+            });
+            run(start(), schedule, &quiet())
+        };
+        let without = plan(&[("Afternoon", 2.0)]);
+        let with = plan(&[("Afternoon", 2.0), ("Sleep", 0.0)]);
+        assert_eq!(without.days, with.days);
+        assert_eq!(without.roi, with.roi);
+        assert_eq!(without.wasted_time, with.wasted_time);
+        let sleep = ("Amu".to_string(), "Sleep".to_string());
+        assert!(with
+            .segment_usage
+            .get(&sleep)
+            .is_none_or(|(used, _)| *used == 0.0));
+    }
+
+    #[test]
+    fn deprioritized_skills_still_complete() {
+        let mut schedule = person(
+            "Amu",
+            &[("Integrity", 2.0), ("Lore", 1.0)],
+            &[("Afternoon", 2.0)],
+        );
+        schedule.extend([
+            // Integrity is first in the default order; make it last.
+            Task::Deprioritize {
+                name: "Amu".into(),
+                skill: "Integrity".into(),
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Integrity", 3.0), ("Lore", 2.0)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        let (_, ranks) = summary.training[&("Amu".to_string(), "Integrity".to_string())];
+        assert!((ranks - 1.0).abs() < EPSILON);
+        // Lore takes the first 24 days, and Integrity the next 48.
+        assert_eq!(
+            summary.first_completion,
+            Some(start() + chrono::Duration::days(23))
+        );
+        assert_eq!(summary.days, 72);
+    }
+
+    #[test]
+    fn combo_order_does_not_matter() {
+        let plan = |pair: Option<[&str; 2]>| {
+            let mut schedule = person(
+                "Amu",
+                &[("Illusion", 1.0), ("Lore", 1.0)],
+                &[("Afternoon", 2.0)],
+            );
+            schedule.extend([
+                Task::Overlap {
+                    name: "Amu".into(),
+                    when: vec![Overlap {
+                        combo: vec!["Illusion".into(), "Lore".into()],
+                        bonus: 1.25,
+                        segment_bonus: btreemap! {},
+                        min_rank: btreemap! {},
+                    }],
+                },
+                Task::Target {
+                    name: "Amu".into(),
+                    target: map(&[("Illusion", 2.0), ("Lore", 2.0)]),
+                },
+            ]);
+            if let Some(skills) = pair {
+                schedule.push(Task::Pair {
+                    name: "Amu".into(),
+                    skills: skills.map(String::from).to_vec(),
+                    bonus: 1.5,
+                });
+            }
+            let summary = run(start(), schedule, &quiet());
+            (summary.days, summary.roi)
+        };
+        // A pair repeating the overlap is the same combo, whichever way round
+        // it's written, so the first bonus stands.
+        let overlap_only = plan(None);
+        assert_eq!(plan(Some(["Illusion", "Lore"])), overlap_only);
+        assert_eq!(plan(Some(["Lore", "Illusion"])), overlap_only);
+    }
+
+    #[test]
+    fn bonuses_below_the_preference_ratio_are_ignored() {
+        // With this offset, Integrity's preference is 1.3 and Dreamwalking's
+        // 1.2, so their combo needs a bonus above 1.3 / 1.25 = 1.04.
+        let plan = |bonus: f32, validate_only: bool| {
+            let mut schedule = person(
+                "Amu",
+                &[("Dreamwalking", 1.0), ("Integrity", 1.0)],
+                &[("Afternoon", 2.0)],
+            );
+            schedule.extend([
+                Task::Pair {
+                    name: "Amu".into(),
+                    skills: vec!["Dreamwalking".into(), "Integrity".into()],
+                    bonus,
+                },
+                Task::Target {
+                    name: "Amu".into(),
+                    target: map(&[("Dreamwalking", 2.0), ("Integrity", 2.0)]),
+                },
+            ]);
+            let options = Options {
+                validate_only,
+                priority_offset: 0.1,
+                ..quiet()
+            };
+            run(start(), schedule, &options)
+        };
+        // Training both at once only pays off if the combo is used.
+        assert!(plan(1.05, false).days < plan(1.03, false).days);
+        assert!(plan(1.05, true).problems.is_empty());
+        assert_eq!(plan(1.03, true).problems.len(), 1);
+    }
+
+    #[test]
+    fn requirements_are_costed_when_they_start() {
+        let not_before = start() + chrono::Duration::days(60);
+        let plan = |head_start: bool| {
+            let mut schedule = person("Amu", &[("Integrity", 1.0)], &[("Afternoon", 2.0)]);
+            if head_start {
+                schedule.push(Task::Target {
+                    name: "Amu".into(),
+                    target: map(&[("Integrity", 2.0)]),
+                });
+            }
+            schedule.push(Task::Requirement {
+                name: "Amu".into(),
+                skill: "Integrity".into(),
+                rank: 3.0,
+                not_before,
+            });
+            let summary = run(start(), schedule, &quiet());
+            let (hours, _) = summary.training[&("Amu".to_string(), "Integrity".to_string())];
+            (summary.last_completion.unwrap(), hours)
+        };
+        let (late, cold) = plan(false);
+        let (early, warm) = plan(true);
+        // Rank 2 was reached before the requirement started, so only the
+        // last rank was left to cost.
+        assert!(early < late);
+        assert!((cold - warm).abs() < 1e-2);
+        // Without it, both ranks take 144 hours at two a day, starting on
+        // the day it's due.
+        assert_eq!(late, not_before + chrono::Duration::days(71));
+    }
+
+    #[test]
+    fn completion_is_reported_once() {
+        let mut person = Person::new("Amu".into(), map(&[("Lore", 1.0)]), DEFAULT_PRIORITY_OFFSET);
+        person.schedule = map(&[("Afternoon", 2.0)]);
+        person.overlap.push(Overlap {
+            combo: vec!["Lore".into()],
+            bonus: 1.0,
+            segment_bonus: btreemap! {},
+            min_rank: btreemap! {},
+        });
+        let target = new_target(&mut person, "Lore", 2.0);
+        person.target.insert("Lore".into(), target);
+        let mut persons = btreemap! { "Amu".to_string() => person };
+        // Keep simulating well past the 24 days Lore needs.
+        let mut completed = vec![];
+        for day in 0..48 {
+            let now = start() + chrono::Duration::days(day);
+            for result in simulate_day(&mut persons, now, &Options::default()) {
+                completed.extend(result.completed);
+            }
+        }
+        assert_eq!(completed, [("Lore".to_string(), 2.0)]);
+        assert!(persons["Amu"].target.is_empty());
+        assert_eq!(persons["Amu"].skills["Lore"], 2.0);
+    }
+
+    #[test]
+    fn boosts_raise_roi_until_they_end() {
+        let until = start() + chrono::Duration::days(4);
+        let mut schedule = person("Amu", &[("Lore", 1.0)], &[("Afternoon", 2.0)]);
+        schedule.extend([
+            Task::Boost {
+                name: "Amu".into(),
+                combo: None,
+                multiplier: 2.0,
+                until,
+            },
+            Task::Observe {
+                name: "Amu".into(),
+                metric: Metric::TotalRoi,
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Lore", 2.0)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        let roi = &summary.metrics[&("Amu".to_string(), Metric::TotalRoi)];
+        assert_eq!(roi[&start()], 4.0);
+        assert_eq!(roi[&until], 4.0);
+        assert_eq!(roi[&until.succ_opt().unwrap()], 2.0);
+    }
+
+    #[test]
+    fn prefer_only_reweights_its_skill() {
+        let order = |prefer: Option<f32>| {
+            let skills = [("Illusion", 1.0), ("Integrity", 1.0), ("Lore", 1.0)];
+            let mut schedule = person("Amu", &skills, &[("Afternoon", 2.0)]);
+            if let Some(weight) = prefer {
+                schedule.push(Task::Prefer {
+                    name: "Amu".into(),
+                    skill: "Lore".into(),
+                    weight,
+                });
+            }
+            schedule.push(Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Illusion", 2.0), ("Integrity", 2.0), ("Lore", 2.0)]),
+            });
+            let summary = run(start(), schedule, &quiet());
+            let mut finished: BTreeMap<Skill, NaiveDate> = btreemap! {};
+            for (date, _, skill, _, ranks) in summary.skill_log.iter() {
+                if *ranks >= 1.0 - EPSILON {
+                    finished.entry(skill.clone()).or_insert(*date);
+                }
+            }
+            let mut order: Vec<(Skill, NaiveDate)> = finished.into_iter().collect();
+            order.sort_by_key(|(_, date)| *date);
+            order
+                .into_iter()
+                .map(|(skill, _)| skill)
+                .collect::<Vec<_>>()
+        };
+        // By default, the priority order trains Lore last.
+        assert_eq!(order(None), ["Integrity", "Illusion", "Lore"]);
+        // Doubling it moves Lore ahead, and leaves the rest in their order.
+        assert_eq!(order(Some(2.0)), ["Lore", "Integrity", "Illusion"]);
+    }
+
+    #[test]
+    fn half_step_skills_reach_half_ranks() {
+        let mut schedule = person("Amu", &[("Lore", 1.0)], &[("Afternoon", 2.0)]);
+        schedule.extend([
+            Task::RankStep {
+                name: "Amu".into(),
+                step: map(&[("Lore", 0.5)]),
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Lore", 2.5)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        let (hours, ranks) = summary.training[&("Amu".to_string(), "Lore".to_string())];
+        assert!((ranks - 1.5).abs() < EPSILON);
+        // Each half rank is costed from where it starts, so 1.5 to 2 costs
+        // more than 1 to 1.5 would.
+        let expected: f32 = [1.0, 1.5, 2.0]
+            .iter()
+            .map(|rank| hours_per_rank("Lore", *rank) / 2.0)
+            .sum();
+        assert!((hours - expected).abs() < 1e-2);
+    }
+
+    #[test]
+    fn relearning_a_lost_rank_is_cheaper() {
+        let hours = |lost: bool| {
+            let rank = if lost { 2.0 } else { 1.0 };
+            let skills = map(&[("Lore", rank)]);
+            let mut person = Person::new("Amu".into(), skills, DEFAULT_PRIORITY_OFFSET);
+            if lost {
+                person.set_rank("Lore", 1.0);
+            }
+            new_target(&mut person, "Lore", 2.0).hours_needed
+        };
+        let fresh = hours(false);
+        assert!((fresh - hours_per_rank("Lore", 1.0)).abs() < 1e-3);
+        assert!((hours(true) - fresh * RELEARN_DISCOUNT).abs() < 1e-3);
+    }
+
+    #[test]
+    fn overlaps_unlock_at_their_minimum_rank() {
+        let mut schedule = person(
+            "Amu",
+            &[("Integrity", 1.0), ("Lore", 1.0)],
+            &[("Afternoon", 2.0)],
+        );
+        schedule.extend([
+            Task::Overlap {
+                name: "Amu".into(),
+                when: vec![Overlap {
+                    combo: vec!["Integrity".into(), "Lore".into()],
+                    bonus: 1.5,
+                    segment_bonus: btreemap! {},
+                    min_rank: map(&[("Lore", 2.0)]),
+                }],
+            },
+            // Lore first, so that it gets to rank 2 while there's still
+            // Integrity left to train.
+            Task::Prefer {
+                name: "Amu".into(),
+                skill: "Lore".into(),
+                weight: 2.0,
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Integrity", 3.0), ("Lore", 3.0)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        let unlocked = summary
+            .skill_log
+            .iter()
+            .find(|(_, _, skill, _, ranks)| skill == "Lore" && *ranks >= 1.0 - EPSILON)
+            .map(|(date, ..)| *date)
+            .unwrap();
+        let combo = vec!["Integrity".to_string(), "Lore".to_string()];
+        let weeks_used: Vec<NaiveDate> = summary
+            .weekly_plan
+            .iter()
+            .filter(|(_, plan)| plan.contains_key(&combo))
+            .map(|((_, monday), _)| *monday)
+            .collect();
+        assert!(!weeks_used.is_empty());
+        assert!(weeks_used
+            .iter()
+            .all(|monday| *monday + chrono::Duration::days(6) >= unlocked));
+    }
+
+    #[test]
+    fn advancing_no_days_is_a_no_op() {
+        let schedule = vec![
+            Task::Advance { days: 0 },
+            Task::At {
+                date: start().succ_opt().unwrap(),
+            },
+        ];
+        run(start(), schedule, &quiet());
+    }
+
+    #[test]
+    fn rollback_forgets_mirrors() {
+        let mut schedule = person("Amu", &[("Lore", 1.0)], &[("Evening", 1.0)]);
+        schedule.extend(person("Bo", &[("Lore", 1.0)], &[("Evening", 1.0)]));
+        schedule.extend([
+            Task::Checkpoint,
+            Task::Mirror {
+                follower: "Bo".into(),
+                leader: "Amu".into(),
+            },
+            Task::Rollback,
+            Task::Schedule {
+                name: "Amu".into(),
+                segment: map(&[("Evening", 1.0), ("Afternoon", 2.0)]),
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Lore", 2.0)]),
+            },
+            Task::Target {
+                name: "Bo".into(),
+                target: map(&[("Lore", 2.0)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        let afternoon = |name: &str| (name.to_string(), "Afternoon".to_string());
+        assert!(summary.segment_usage.contains_key(&afternoon("Amu")));
+        assert!(!summary.segment_usage.contains_key(&afternoon("Bo")));
+    }
+
+    #[test]
+    fn warm_start_finds_equally_good_plans() {
+        // Names with spaces end up in the LP's variable names.
+        let mut schedule = person(
+            "Amu",
+            &[("Integrity", 2.0), ("Martial Arts", 1.0)],
+            &[("Afternoon", 2.0), ("Late evening", 1.0)],
+        );
+        schedule.extend([
+            Task::SafetyLimit {
+                name: "Amu".into(),
+                limit: map(&[("Integrity", 0.5)]),
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Integrity", 3.0), ("Martial Arts", 3.0)]),
+            },
+        ]);
+        let cold = run(start(), schedule.clone(), &quiet());
+        let warm_start = Options {
+            warm_start: true,
+            ..quiet()
+        };
+        let warm = run(start(), schedule, &warm_start);
+        assert_eq!(cold.completions, warm.completions);
+        assert!((cold.roi - warm.roi).abs() < 1e-3);
+    }
+
+    #[test]
+    fn max_concurrent_must_allow_a_skill() {
+        let args = ["--max-concurrent", "0"].map(String::from);
+        assert!(Options::from_args(args).is_err());
+        let args = ["--max-concurrent", "1"].map(String::from);
+        assert_eq!(Options::from_args(args).unwrap().max_concurrent, Some(1));
+    }
+
+    #[test]
+    fn completion_sticks_through_decay() {
+        let mut schedule = person(
+            "Amu",
+            &[("Integrity", 1.0), ("Lore", 1.0)],
+            &[("Afternoon", 4.0)],
+        );
+        schedule.extend([
+            // Upkeep is missed three days a week, so Lore keeps slipping
+            // back under the rank it just reached.
+            Task::Availability {
+                name: "Amu".into(),
+                unavailable: vec![
+                    DatePattern::Weekday(chrono::Weekday::Wed),
+                    DatePattern::Weekday(chrono::Weekday::Sat),
+                    DatePattern::Weekday(chrono::Weekday::Sun),
+                ],
+            },
+            Task::Maintenance {
+                name: "Amu".into(),
+                skill: "Lore".into(),
+                hours_per_week: 7.0,
+            },
+            Task::Prefer {
+                name: "Amu".into(),
+                skill: "Lore".into(),
+                weight: 2.0,
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Integrity", 3.0), ("Lore", 2.0)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        let lore: Vec<_> = summary
+            .completions
+            .iter()
+            .filter(|(_, _, skill)| skill == "Lore")
+            .collect();
+        assert_eq!(lore.len(), 1);
+        assert!(summary.final_ranks["Amu"]["Lore"] < 2.0);
+    }
+
+    #[test]
+    fn max_concurrent_caps_skills_per_day() {
+        let skills = [("Illusion", 1.0), ("Integrity", 1.0), ("Lore", 1.0)];
+        let mut schedule = person("Amu", &skills, &[("Afternoon", 2.0), ("Evening", 1.0)]);
+        schedule.extend([
+            Task::Pair {
+                name: "Amu".into(),
+                skills: vec!["Illusion".into(), "Integrity".into(), "Lore".into()],
+                bonus: 1.5,
+            },
+            Task::MaxConcurrent {
+                name: "Amu".into(),
+                n: 1,
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Illusion", 2.0), ("Integrity", 2.0), ("Lore", 2.0)]),
+            },
+        ]);
+        let summary = run(start(), schedule, &quiet());
+        assert_eq!(summary.completions.len(), 3);
+        let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for (date, _, _, hours, _) in summary.skill_log.iter() {
+            if *hours > EPSILON {
+                *per_day.entry(*date).or_default() += 1;
+            }
+        }
+        assert!(per_day.values().all(|count| *count <= 1));
+    }
+
+    #[test]
+    fn lp_names_are_single_words() {
+        let name = format!("combo_{}_{}", "Late evening", "Martial Arts");
+        assert_eq!(lp_name(&name), "combo_Late_evening_Martial_Arts");
+    }
+
+    #[test]
+    fn mps_dumps_keep_names_whole() {
+        let dir = std::env::temp_dir().join(format!("shards-mps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut schedule = person(
+            "Amu Ito",
+            &[("Martial Arts", 1.0)],
+            &[("Late evening", 2.0)],
+        );
+        schedule.extend([
+            Task::DebugProblem {
+                name: "Amu Ito".into(),
+                date: start(),
+            },
+            Task::Target {
+                name: "Amu Ito".into(),
+                target: map(&[("Martial Arts", 2.0)]),
+            },
+        ]);
+        let options = Options {
+            dump_mps: Some(dir.to_str().unwrap().to_string()),
+            ..quiet()
+        };
+        run(start(), schedule, &options);
+        let mut dumps = 0;
+        for file in std::fs::read_dir(&dir).unwrap() {
+            let mps = std::fs::read_to_string(file.unwrap().path()).unwrap();
+            // Every field is a single word, so each kind of line has a fixed
+            // number of them.
+            let mut section = "";
+            for line in mps.lines() {
+                let fields = line.split_whitespace().count();
+                if line.starts_with(' ') {
+                    let expected = match section {
+                        "OBJSENSE" => 1,
+                        "ROWS" => 2,
+                        _ => 3,
+                    };
+                    assert_eq!(fields, expected, "{}", line);
+                } else if line.starts_with("NAME") {
+                    assert_eq!(fields, 2, "{}", line);
+                } else {
+                    section = line;
+                }
+            }
+            dumps += 1;
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(dumps > 0);
+    }
+
+    #[test]
+    fn problem_dumps_name_values_as_the_lp_does() {
+        let person = Person::new(
+            "Amu".into(),
+            map(&[("Martial Arts", 1.0)]),
+            DEFAULT_PRIORITY_OFFSET,
+        );
+        let roi = LpContinuous::new(&lp_name("ROI_Martial Arts"));
+        let mut problem = LpProblem::new("test", LpObjective::Maximize);
+        problem += &roi * 1.0;
+        problem += constraint!(roi <= 2.0);
+        let solution = solvers::MiniLpSolver::new().run(&problem).unwrap();
+        let mut out = vec![];
+        dump_problem(
+            &person,
+            &start(),
+            Relaxation::None,
+            &problem,
+            &solution,
+            &mut out,
+        )
+        .unwrap();
+        let dump = String::from_utf8(out).unwrap();
+        let (lp, values) = dump.split_once("Values:").unwrap();
+        let (values, _) = values.split_once("Slack:").unwrap();
+        let lp: BTreeSet<&str> = lp.split_whitespace().collect();
+        for line in values.lines().filter(|line| !line.is_empty()) {
+            let [name, "=", _] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                panic!("Malformed value: {}", line);
+            };
+            assert!(lp.contains(name), "{} is not in the LP", name);
+        }
+    }
+
+    #[test]
+    fn completion_dates_give_up_after_max_days() {
+        let skills = map(&[("Integrity", 1.0), ("Lore", 1.0)]);
+        let mut person = Person::new("Amu".into(), skills, DEFAULT_PRIORITY_OFFSET);
+        person.schedule = map(&[("Afternoon", 2.0)]);
+        // Lore goes first and needs 48 hours. Integrity needs far more than
+        // the rest of 60 days.
+        person.preference.insert("Lore".into(), 10.0);
+        for (skill, rank) in [("Integrity", 3.0), ("Lore", 2.0)] {
+            person.overlap.push(Overlap {
+                combo: vec![skill.into()],
+                bonus: 1.0,
+                segment_bonus: btreemap! {},
+                min_rank: btreemap! {},
+            });
+            let target = new_target(&mut person, skill, rank);
+            person.target.insert(skill.into(), target);
+        }
+        let persons = btreemap! { "Amu".to_string() => person };
+        let options = Options {
+            max_days: 60,
+            ..quiet()
+        };
+        let skills = ["Integrity", "Lore"];
+        let dates = completion_dates(persons.clone(), start(), "Amu", &skills, &options);
+        assert!(dates["Lore"].is_some());
+        assert_eq!(dates["Integrity"], None);
+        // Once the budget runs out, neither ever will.
+        let mut persons = persons;
+        persons.get_mut("Amu").unwrap().lifetime_budget = Some(0.5);
+        let dates = completion_dates(persons, start(), "Amu", &skills, &Options::default());
+        assert_eq!(dates["Lore"], None);
+        assert_eq!(dates["Integrity"], None);
+    }
+
+    #[test]
+    fn balanced_targets_leave_upkeep_first() {
+        let skills = map(&[("Integrity", 1.0), ("Lore", 1.0)]);
+        let mut person = Person::new("Amu".into(), skills, DEFAULT_PRIORITY_OFFSET);
+        person.schedule = map(&[("Afternoon", 2.0)]);
+        person.overlap.push(Overlap {
+            combo: vec!["Integrity".into()],
+            bonus: 1.0,
+            segment_bonus: btreemap! {},
+            min_rank: btreemap! {},
+        });
+        // Thousands of hours, so that weighting by them alone would outbid
+        // upkeep.
+        let target = new_target(&mut person, "Integrity", 9.0);
+        assert!(target.hours_needed > MAINTENANCE_WEIGHT);
+        person.target.insert("Integrity".into(), target);
+        person.maintenance.insert(
+            "Lore".into(),
+            Maintenance {
+                hours_per_week: 7.0,
+                done: VecDeque::new(),
+            },
+        );
+        let options = Options {
+            balanced: true,
+            ..quiet()
+        };
+        let mut persons = btreemap! { "Amu".to_string() => person };
+        let results = simulate_day(&mut persons, start(), &options);
+        assert!((results[0].maintenance["Lore"] - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn spill_wastes_less_time() {
+        let mut schedule = person(
+            "Amu",
+            &[("Integrity", 1.0), ("Lore", 1.0)],
+            &[("Afternoon", 2.0), ("Evening", 2.5)],
+        );
+        schedule.extend([
+            // Each skill has a segment to itself, so once Integrity is nearly
+            // done, most of its last evening would go idle.
+            Task::ScheduleLimit {
+                name: "Amu".into(),
+                limit: btreemap! {
+                    "Afternoon".to_string() => vec!["Lore".to_string()],
+                    "Evening".to_string() => vec!["Integrity".to_string()],
+                },
+                focus: btreemap! {},
+            },
+            Task::Target {
+                name: "Amu".into(),
+                target: map(&[("Integrity", 2.0), ("Lore", 2.0)]),
+            },
+        ]);
+        let without = run(start(), schedule.clone(), &quiet());
+        let spill = Options {
+            spill: true,
+            ..quiet()
+        };
+        let with = run(start(), schedule, &spill);
+        // The last of Integrity's evening goes to Lore instead.
+        assert!(with.wasted_time < without.wasted_time);
+        assert!(with.days <= without.days);
+    }
 }
@@ -0,0 +1,249 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::NaiveDate;
+
+use crate::types::DEFAULT_PRIORITY_OFFSET;
+
+// Solver residuals below this are treated as zero.
+pub const EPSILON: f32 = 1e-4;
+// The relative difference in objective at which --cross-check warns.
+pub const CROSS_CHECK_TOLERANCE: f32 = 1e-3;
+
+// How to order skills the user has no preference between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Leave it to the solver, and the order of the skills by name.
+    None,
+    // Attributes before abilities before psionics.
+    Category,
+    // Whichever needs the fewest hours to reach its target.
+    Cheapest,
+}
+
+// Command-line options.
+#[derive(Debug, Clone)]
+pub struct Options {
+    // Decimal places used for all numeric output.
+    pub precision: usize,
+    // Weight the objective by remaining hours, so all targets advance together.
+    pub balanced: bool,
+    // Maximize the progress of whichever target is furthest behind.
+    pub maxmin: bool,
+    // Treat preference as strict priority: no amount of a lower-priority
+    // skill is worth any of a higher-priority one.
+    pub lexicographic: bool,
+    // How to order equally-preferred skills.
+    pub tiebreak: TieBreak,
+    // Let ROI beyond what a skill needs go to the next preferred skill, rather
+    // than leaving time idle once a skill is nearly done.
+    pub spill: bool,
+    // Caps how many targets anyone trains per day, overriding Task::MaxConcurrent.
+    pub max_concurrent: Option<usize>,
+    // Rerun with every cap on concurrent targets, and report the tradeoff
+    // between days taken and time wasted.
+    pub pareto: bool,
+    // Rerun without each overlap in turn, and report what each one is worth.
+    pub overlap_value: bool,
+    // How many steps each day is planned in.
+    pub steps_per_day: u32,
+    // Print how much each person could train per day, before simulating.
+    pub capacity: bool,
+    // A hypothetical overlap to price before simulating: the person, the
+    // two skills and the bonus.
+    pub try_overlap: Option<(String, [String; 2], f32)>,
+    // Rank everyone by when they finished all their targets.
+    pub leaderboard: bool,
+    // Print the training done each week, by combo.
+    pub weekly_plan: bool,
+    // Drop safety limits, then schedule limits, on days with no feasible plan.
+    pub auto_relax: bool,
+    // Print every known skill by category, and exit.
+    pub list_skills: bool,
+    // Don't print per-day events.
+    pub quiet: bool,
+    // Solve every plan with CBC as well, and compare objectives.
+    pub cross_check: bool,
+    // Start each solve from the last one for the same person, where MiniLP
+    // can. Ties between equally good plans may then break differently.
+    pub warm_start: bool,
+    // Leave warnings out of the summary.
+    pub no_warnings: bool,
+    // Print targeted skills that made no progress each day.
+    pub starved: bool,
+    // Set up the schedule and check it for problems, without simulating.
+    pub validate_only: bool,
+    // Preference added per step of the default priority order. This must stay
+    // small next to overlap bonuses, or they get ignored; see types.rs.
+    pub priority_offset: f32,
+    // Where to write observed metrics as CSV, instead of stdout.
+    pub metrics: Option<String>,
+    // Where to write hours and ranks per skill per day, as CSV.
+    pub skill_log: Option<String>,
+    // Where to write a Markdown report of the whole run.
+    pub report: Option<String>,
+    // A directory to also write Task::DebugProblem's problems to, as MPS.
+    pub dump_mps: Option<String>,
+    // Where to write a DOT graph of each person's overlaps.
+    pub graph: Option<String>,
+    // A file of per-day results to compare this run against. Written
+    // instead, if it doesn't exist yet.
+    pub golden: Option<String>,
+    // Find the smallest scale of this person's schedule that still meets
+    // all their targets by this date.
+    pub solve_schedule: Option<(String, NaiveDate)>,
+    // Find the lowest shift of this person's starting ranks that still meets
+    // all their targets by this date.
+    pub reverse_plan: Option<(String, NaiveDate)>,
+    // Give up on whatever targets are left once a day's ROI drops below this.
+    pub min_daily_roi: Option<f32>,
+    // Allow negative preferences, which are otherwise taken for typos.
+    pub allow_negative_preference: bool,
+    // Perturb everyone's starting ranks by normal noise with this standard
+    // deviation, and report how much completion dates move across seeds.
+    pub jitter: Option<f32>,
+    // Re-solve every person-day this many times with the objective slightly
+    // perturbed, and warn if the ROI changes.
+    pub perturb_test: Option<u32>,
+    // Seed for --jitter and --perturb-test.
+    pub seed: u64,
+    // Warn up front if anyone's targets can't be met in fewer days than this.
+    // Tradeoff reports also give up on a target after this many days.
+    pub max_days: u32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            precision: 2,
+            balanced: false,
+            maxmin: false,
+            lexicographic: false,
+            tiebreak: TieBreak::None,
+            steps_per_day: 1,
+            max_concurrent: None,
+            spill: false,
+            pareto: false,
+            overlap_value: false,
+            capacity: false,
+            weekly_plan: false,
+            leaderboard: false,
+            try_overlap: None,
+            auto_relax: false,
+            list_skills: false,
+            quiet: false,
+            no_warnings: false,
+            cross_check: false,
+            warm_start: false,
+            starved: false,
+            validate_only: false,
+            priority_offset: DEFAULT_PRIORITY_OFFSET,
+            metrics: None,
+            skill_log: None,
+            graph: None,
+            dump_mps: None,
+            report: None,
+            golden: None,
+            max_days: 3650,
+            solve_schedule: None,
+            reverse_plan: None,
+            jitter: None,
+            perturb_test: None,
+            allow_negative_preference: false,
+            min_daily_roi: None,
+            seed: 0,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+            match arg.as_str() {
+                "--precision" => {
+                    options.precision = value()?.parse().context("--precision")?;
+                }
+                "--priority-offset" => {
+                    options.priority_offset = value()?.parse().context("--priority-offset")?;
+                }
+                "--max-days" => {
+                    options.max_days = value()?.parse().context("--max-days")?;
+                }
+                "--tiebreak" => {
+                    options.tiebreak = match value()?.as_str() {
+                        "none" => TieBreak::None,
+                        "category" => TieBreak::Category,
+                        "cheapest" => TieBreak::Cheapest,
+                        other => bail!("Unknown --tiebreak policy: {}", other),
+                    };
+                }
+                "--solve-schedule" => {
+                    let name = value()?;
+                    let deadline = NaiveDate::parse_from_str(&value()?, "%Y-%m-%d")
+                        .context("--solve-schedule")?;
+                    options.solve_schedule = Some((name, deadline));
+                }
+                "--try-overlap" => {
+                    let name = value()?;
+                    let skills = [value()?, value()?];
+                    let bonus = value()?.parse().context("--try-overlap")?;
+                    options.try_overlap = Some((name, skills, bonus));
+                }
+                "--reverse-plan" => {
+                    let name = value()?;
+                    let deadline = NaiveDate::parse_from_str(&value()?, "%Y-%m-%d")
+                        .context("--reverse-plan")?;
+                    options.reverse_plan = Some((name, deadline));
+                }
+                "--min-daily-roi" => {
+                    options.min_daily_roi = Some(value()?.parse().context("--min-daily-roi")?);
+                }
+                "--jitter" => options.jitter = Some(value()?.parse().context("--jitter")?),
+                "--perturb-test" => {
+                    options.perturb_test = Some(value()?.parse().context("--perturb-test")?);
+                }
+                "--seed" => options.seed = value()?.parse().context("--seed")?,
+                "--metrics" => options.metrics = Some(value()?),
+                "--skill-log" => options.skill_log = Some(value()?),
+                "--graph" => options.graph = Some(value()?),
+                "--dump-mps" => options.dump_mps = Some(value()?),
+                "--report" => options.report = Some(value()?),
+                "--golden" => options.golden = Some(value()?),
+                "--balanced" => options.balanced = true,
+                "--max-concurrent" => {
+                    let n = value()?.parse().context("--max-concurrent")?;
+                    if n == 0 {
+                        bail!("--max-concurrent must be at least 1");
+                    }
+                    options.max_concurrent = Some(n);
+                }
+                "--steps-per-day" => {
+                    options.steps_per_day = value()?.parse().context("--steps-per-day")?;
+                    if options.steps_per_day == 0 {
+                        bail!("--steps-per-day must be at least 1");
+                    }
+                }
+                "--list-skills" => options.list_skills = true,
+                "--auto-relax" => options.auto_relax = true,
+                "--allow-negative-preference" => options.allow_negative_preference = true,
+                "--weekly-plan" => options.weekly_plan = true,
+                "--leaderboard" => options.leaderboard = true,
+                "--capacity" => options.capacity = true,
+                "--pareto" => options.pareto = true,
+                "--overlap-value" => options.overlap_value = true,
+                "--maxmin" => options.maxmin = true,
+                "--spill" => options.spill = true,
+                "--lexicographic" => options.lexicographic = true,
+                "--quiet" => options.quiet = true,
+                "--no-warnings" => options.no_warnings = true,
+                "--cross-check" => options.cross_check = true,
+                "--warm-start" => options.warm_start = true,
+                "--starved" => options.starved = true,
+                "--validate-only" => options.validate_only = true,
+                _ => bail!("Unknown option: {}", arg),
+            }
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,746 @@
+use chrono::{Datelike, NaiveDate};
+use log::{debug, info};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use crate::options::{Options, CROSS_CHECK_TOLERANCE, EPSILON};
+use crate::types::*;
+use crate::{ABILITIES, ATTRIBUTES, PSIONICS};
+
+// A fixed-precision rendering of a day's result, for comparing runs.
+fn golden_line(result: &PersonDayResult) -> String {
+    // Solver residuals would otherwise show up as -0.
+    let fixed = |value: f32| if value.abs() <= EPSILON { 0.0 } else { value };
+    let mut line = format!(
+        "{} {:?} roi={:.4} wasted={:.4}",
+        result.date,
+        result.name,
+        fixed(result.roi),
+        fixed(result.wasted_time)
+    );
+    for (skill, hours) in result.hours.iter() {
+        line += &format!(" {:?}={:.4}", skill, fixed(*hours));
+    }
+    line
+}
+
+// The first pair of lines where a run differs from its golden file, with
+// "<end>" standing in for a missing line.
+pub fn first_divergence<'a>(
+    expected: impl Iterator<Item = &'a str>,
+    actual: &'a [String],
+) -> Option<(&'a str, &'a str)> {
+    let mut expected = expected.fuse();
+    let mut actual = actual.iter().map(|line| line.as_str());
+    loop {
+        match (expected.next(), actual.next()) {
+            (None, None) => return None,
+            (e, a) if e == a => continue,
+            (e, a) => return Some((e.unwrap_or("<end>"), a.unwrap_or("<end>"))),
+        }
+    }
+}
+
+// Writes a parameter sweep as CSV, marking the points that no other point
+// beats on both days and wasted time.
+pub fn write_pareto(points: &[(usize, usize, f32)], out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "max_concurrent,days,wasted_time,frontier")?;
+    for (n, days, wasted) in points.iter() {
+        let dominated = points
+            .iter()
+            .any(|(_, d, w)| d <= days && w <= wasted && (d < days || wasted - w > EPSILON));
+        writeln!(out, "{},{},{},{}", n, days, wasted, !dominated)?;
+    }
+    Ok(())
+}
+
+// Prints the range of completion dates seen over several jittered runs.
+pub fn print_jitter_spread(dates: &BTreeMap<(Name, Skill), Vec<NaiveDate>>, runs: u64) {
+    println!("Completion dates over {} jittered runs:", runs);
+    for ((name, skill), dates) in dates.iter() {
+        let (Some(first), Some(last)) = (dates.iter().min(), dates.iter().max()) else {
+            continue;
+        };
+        println!(
+            "  {} {}: {} to {} ({} days)",
+            name,
+            skill,
+            first,
+            last,
+            (*last - *first).num_days()
+        );
+    }
+}
+
+// Prints the human-readable events of a simulated day.
+pub fn print_day(results: &[PersonDayResult], options: &Options) {
+    for result in results {
+        debug!(
+            "{}: {} trained {:?}",
+            result.date, result.name, result.hours
+        );
+        for (skill, rank) in result.completed.iter() {
+            println!(
+                "{}: {} has reached target rank of {:.*} for {}",
+                result.date, result.name, options.precision, rank, skill
+            );
+            info!(
+                "event=skill_complete date={} person={:?} skill={:?} rank={}",
+                result.date, result.name, skill, rank
+            );
+        }
+        for (skill, rank, deadline) in result.checkpoints.iter() {
+            let days = (*deadline - result.date).num_days();
+            let verdict = if days >= 0 {
+                format!("{} days ahead of", days)
+            } else {
+                format!("{} days after", -days)
+            };
+            println!(
+                "{}: {} reached checkpoint {:.*} for {}, {} its deadline of {}",
+                result.date, result.name, options.precision, rank, skill, verdict, deadline
+            );
+        }
+        for relaxed in result.relaxed.iter() {
+            println!("{}: {} had to {}", result.date, result.name, relaxed);
+        }
+        for (skill, lost) in result.decay.iter() {
+            println!(
+                "{}: {} lost {:.*} effective hours of {} to missed upkeep",
+                result.date, result.name, options.precision, lost, skill
+            );
+        }
+        if !result.abandoned.is_empty() {
+            println!(
+                "{}: {} has run out of training budget, leaving {} unmet",
+                result.date,
+                result.name,
+                result.abandoned.join(", ")
+            );
+        }
+        if options.starved {
+            let starved: Vec<Skill> = result
+                .hours
+                .iter()
+                .filter(|(_, hours)| **hours <= EPSILON)
+                .map(|(skill, _)| skill.clone())
+                .collect();
+            if !starved.is_empty() {
+                println!(
+                    "{}: {} made no progress on {}",
+                    result.date,
+                    result.name,
+                    starved.join(", ")
+                );
+            }
+        }
+    }
+}
+
+// Writes a DOT graph of each person's skills, with overlaps as edges
+// labelled by their bonus. Combos of more than two skills get a point node
+// joined to each of their skills.
+pub fn write_graph<'a>(
+    persons: impl Iterator<Item = &'a Person>,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(out, "graph overlaps {{")?;
+    for (i, person) in persons.enumerate() {
+        writeln!(out, "  subgraph cluster_{} {{", i)?;
+        writeln!(out, "    label={:?};", person.name)?;
+        let node = |skill: &str| format!("{:?}", format!("{}/{}", person.name, skill));
+        for skill in person.skills.keys() {
+            writeln!(out, "    {} [label={:?}];", node(skill), skill)?;
+        }
+        for (j, overlap) in person.overlap.iter().enumerate() {
+            match overlap.combo.as_slice() {
+                [_] => {}
+                [a, b] => writeln!(
+                    out,
+                    "    {} -- {} [label=\"{}\"];",
+                    node(a),
+                    node(b),
+                    overlap.bonus
+                )?,
+                combo => {
+                    let hub = format!("\"{}/combo{}\"", person.name, j);
+                    writeln!(
+                        out,
+                        "    {} [shape=point, xlabel=\"{}\"];",
+                        hub, overlap.bonus
+                    )?;
+                    for skill in combo {
+                        writeln!(out, "    {} -- {};", hub, node(skill))?;
+                    }
+                }
+            }
+        }
+        writeln!(out, "  }}")?;
+    }
+    writeln!(out, "}}")
+}
+
+// Totals accumulated over the days of a run.
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+    pub roi: f32,
+    pub wasted_time: f32,
+    pub days: usize,
+    // The (ROI, date) of the best and worst days seen so far.
+    pub best_day: Option<(f32, NaiveDate)>,
+    pub worst_day: Option<(f32, NaiveDate)>,
+    // Segment usage, keyed by person and segment, as (used, available) hours.
+    pub segment_usage: BTreeMap<(Name, Segment), (f32, f32)>,
+    // The dates of the first and last target completions.
+    pub first_completion: Option<NaiveDate>,
+    pub last_completion: Option<NaiveDate>,
+    // Every target completion, in order.
+    pub completions: Vec<(NaiveDate, Name, Skill)>,
+    // Effective hours trained and ranks gained, by person and skill.
+    pub training: BTreeMap<(Name, Skill), (f32, f32)>,
+    // Problems found when validating the schedule.
+    pub problems: Vec<String>,
+    // Observed metrics, by person and metric, then by date.
+    pub metrics: BTreeMap<(Name, Metric), BTreeMap<NaiveDate, f32>>,
+    // Targets that were given up on, by person.
+    pub unmet: Vec<(Name, Skill)>,
+    // Whether the run was cut short by --min-daily-roi.
+    pub stopped_early: bool,
+    // Every day's results, one line per person, in a stable format for --golden.
+    pub golden: Vec<String>,
+    // Combos that got training time on any day, by person.
+    pub combos_used: BTreeMap<Name, BTreeSet<Vec<Skill>>>,
+    // Multi-skill overlaps that never got used, by person.
+    pub unused_overlaps: BTreeMap<Name, Vec<Vec<Skill>>>,
+    // Days whose plan the solver didn't solve to optimality, with its status.
+    pub non_optimal: Vec<(NaiveDate, Name, String)>,
+    // Everything warned about during the run, in order.
+    pub warnings: Vec<Warning>,
+    // The day MiniLP and CBC disagreed on most, and by how much, with
+    // --cross-check.
+    pub worst_disagreement: Option<(f32, NaiveDate, Name)>,
+    // Schedule hours spent on each combo, by person and the Monday of each week.
+    pub weekly_plan: BTreeMap<(Name, NaiveDate), BTreeMap<Vec<Skill>, f32>>,
+    // Neglect penalties charged, by person and skill.
+    pub penalties: BTreeMap<(Name, Skill), f32>,
+    // Hours of upkeep done and effective hours lost to decay, by person and
+    // skill.
+    pub maintenance: BTreeMap<(Name, Skill), (f32, f32)>,
+    // ROI over every simulated day so far, including those before the
+    // schedule ran out, by person.
+    pub roi_so_far: BTreeMap<Name, f32>,
+    // The same for wasted time.
+    pub wasted_so_far: BTreeMap<Name, f32>,
+    // Everyone's ranks at the end of the run.
+    pub final_ranks: BTreeMap<Name, BTreeMap<Skill, f32>>,
+    // Hours trained and ranks gained so far, by date, person and skill.
+    pub skill_log: Vec<(NaiveDate, Name, Skill, f32, f32)>,
+}
+
+// The value of a metric for one person-day.
+fn evaluate_metric(metric: Metric, result: &PersonDayResult) -> f32 {
+    let category_roi = |category: &BTreeSet<&str>| {
+        result
+            .hours
+            .iter()
+            .filter(|(skill, _)| category.contains(skill.as_str()))
+            .map(|(_, hours)| hours)
+            .sum()
+    };
+    match metric {
+        Metric::TotalRoi => result.roi,
+        Metric::WastedTime => result.wasted_time,
+        Metric::AttributeRoi => category_roi(&ATTRIBUTES),
+        Metric::AbilityRoi => category_roi(&ABILITIES),
+        Metric::PsionicRoi => category_roi(&PSIONICS),
+    }
+}
+
+impl RunSummary {
+    // Records the metrics each person is observing.
+    pub fn observe(&mut self, persons: &BTreeMap<Name, Person>, results: &[PersonDayResult]) {
+        for result in results {
+            for metric in persons[&result.name].observe.iter() {
+                self.metrics
+                    .entry((result.name.clone(), *metric))
+                    .or_default()
+                    .insert(result.date, evaluate_metric(*metric, result));
+            }
+        }
+    }
+
+    // Writes the observed metrics as CSV, one row per date and one column
+    // per person and metric.
+    pub fn write_metrics(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let dates: BTreeSet<NaiveDate> = self
+            .metrics
+            .values()
+            .flat_map(|series| series.keys().cloned())
+            .collect();
+        write!(out, "date")?;
+        for (name, metric) in self.metrics.keys() {
+            write!(out, ",{} {}", name, metric.name())?;
+        }
+        writeln!(out)?;
+        for date in dates {
+            write!(out, "{}", date)?;
+            for series in self.metrics.values() {
+                match series.get(&date) {
+                    // Solver residuals would otherwise show up as -0.
+                    Some(value) if value.abs() <= EPSILON => write!(out, ",0")?,
+                    Some(value) => write!(out, ",{}", value)?,
+                    None => write!(out, ",")?,
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    // Compares each person's overlaps with the combos actually used. Those
+    // never used are dead weight, or have bonuses too low to matter.
+    pub fn note_unused_overlaps<'a>(&mut self, persons: impl Iterator<Item = &'a Person>) {
+        for person in persons {
+            let used = self.combos_used.get(&person.name);
+            let unused: Vec<Vec<Skill>> = person
+                .overlap
+                .iter()
+                .filter(|o| o.combo.len() > 1)
+                .filter(|o| used.is_none_or(|used| !used.contains(&o.combo)))
+                .map(|o| o.combo.clone())
+                .collect();
+            self.unused_overlaps.insert(person.name.clone(), unused);
+        }
+    }
+
+    pub fn note_final_ranks<'a>(&mut self, persons: impl Iterator<Item = &'a Person>) {
+        for person in persons {
+            self.final_ranks
+                .insert(person.name.clone(), person.skills.clone());
+        }
+    }
+
+    // Writes a Markdown report of the whole run, for --report.
+    pub fn write_report(&self, out: &mut impl Write, precision: usize) -> std::io::Result<()> {
+        writeln!(out, "# Training report")?;
+        for (name, ranks) in self.final_ranks.iter() {
+            writeln!(out)?;
+            writeln!(out, "## {}", name)?;
+            writeln!(out)?;
+            writeln!(
+                out,
+                "Total ROI {:.*}, wasted time {:.*} hours.",
+                precision,
+                self.roi_so_far.get(name).copied().unwrap_or(0.0),
+                precision,
+                self.wasted_so_far.get(name).copied().unwrap_or(0.0)
+            )?;
+            writeln!(out)?;
+            writeln!(out, "| Skill | Final rank |")?;
+            writeln!(out, "|---|---|")?;
+            for (skill, rank) in ranks.iter() {
+                writeln!(out, "| {} | {:.*} |", skill, precision, rank)?;
+            }
+            let completions: Vec<_> = self
+                .completions
+                .iter()
+                .filter(|(_, n, _)| n == name)
+                .collect();
+            if !completions.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "Completed:")?;
+                writeln!(out)?;
+                for (date, _, skill) in completions {
+                    writeln!(out, "- {}: {}", date, skill)?;
+                }
+            }
+            let mut by_category: BTreeMap<&str, f32> = BTreeMap::new();
+            for (_, _, skill, hours, _) in self.skill_log.iter().filter(|(_, n, ..)| n == name) {
+                let category = if ATTRIBUTES.contains(skill.as_str()) {
+                    "Attributes"
+                } else if ABILITIES.contains(skill.as_str()) {
+                    "Abilities"
+                } else if PSIONICS.contains(skill.as_str()) {
+                    "Psionics"
+                } else {
+                    "Other"
+                };
+                *by_category.entry(category).or_insert(0.0) += hours;
+            }
+            if !by_category.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "| Category | Effective hours |")?;
+                writeln!(out, "|---|---|")?;
+                for (category, hours) in by_category.iter() {
+                    writeln!(out, "| {} | {:.*} |", category, precision, hours)?;
+                }
+            }
+            let warnings: Vec<_> = self
+                .warnings
+                .iter()
+                .filter(|w| w.name.as_ref() == Some(name))
+                .collect();
+            if !warnings.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "Warnings:")?;
+                writeln!(out)?;
+                for warning in warnings {
+                    writeln!(out, "- {:?}: {}", warning.kind, warning.message)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Prints how many days each overlap saves, and how much ROI per day it
+    // adds, next to runs without it. The values are the (days, ROI) of those
+    // runs, from --overlap-value.
+    pub fn print_overlap_values(
+        &self,
+        values: &[(Name, Vec<Skill>, usize, f32)],
+        precision: usize,
+    ) {
+        let per_day = |roi: f32, days: usize| if days == 0 { 0.0 } else { roi / days as f32 };
+        println!("Value of each overlap, from runs without it:");
+        for (name, combo, days, roi) in values.iter() {
+            println!(
+                "  {} {}: saves {} days, {:+.*} ROI/day",
+                name,
+                combo.join(" + "),
+                *days as i64 - self.days as i64,
+                precision,
+                per_day(self.roi, self.days) - per_day(*roi, *days)
+            );
+        }
+    }
+
+    // Prints where some people stand partway through a run.
+    pub fn print_snapshot(&self, now: NaiveDate, persons: &[&Person], precision: usize) {
+        println!("{}: Status report", now);
+        for person in persons {
+            let roi = self.roi_so_far.get(&person.name).copied().unwrap_or(0.0);
+            println!("  {}: ROI so far {:.*}", person.name, precision, roi);
+            for skill in person.skills.keys() {
+                let rank = crate::current_rank(person, skill);
+                match person.target.get(skill) {
+                    Some(target) => println!(
+                        "    {}: {:.*} -> {:.*} ({:.*} hours left)",
+                        skill,
+                        precision,
+                        rank,
+                        precision,
+                        target.target_rank,
+                        precision,
+                        target.hours_needed
+                    ),
+                    None => println!("    {}: {:.*}", skill, precision, rank),
+                }
+            }
+        }
+    }
+
+    // Prints everyone who completed a target, ranked by when they finished
+    // the last one. Anyone who gave up on a target comes last.
+    pub fn print_leaderboard(&self, start: NaiveDate) {
+        let mut finished: BTreeMap<Name, NaiveDate> = BTreeMap::new();
+        for (date, name, _) in self.completions.iter() {
+            finished.insert(name.clone(), *date);
+        }
+        let unfinished: BTreeSet<Name> = self.unmet.iter().map(|(name, _)| name.clone()).collect();
+        let mut ranking: Vec<(Name, NaiveDate)> = finished
+            .into_iter()
+            .filter(|(name, _)| !unfinished.contains(name))
+            .collect();
+        ranking.sort_by_key(|(name, date)| (*date, name.clone()));
+        println!("Leaderboard:");
+        for (place, (name, date)) in ranking.iter().enumerate() {
+            println!(
+                "  {}. {}: done {} ({} days)",
+                place + 1,
+                name,
+                date,
+                (*date - start).num_days() + 1
+            );
+        }
+        for name in unfinished {
+            println!("  -. {}: unfinished", name);
+        }
+    }
+
+    // Prints the training each person did, week by week, busiest combos first.
+    pub fn print_weekly_plan(&self, precision: usize) {
+        for ((name, monday), combos) in self.weekly_plan.iter() {
+            let mut combos: Vec<(&Vec<Skill>, &f32)> = combos.iter().collect();
+            combos.sort_by(|a, b| b.1.total_cmp(a.1));
+            let plan: Vec<String> = combos
+                .iter()
+                .map(|(combo, hours)| format!("{:.*}h {}", precision, hours, combo.join("+")))
+                .collect();
+            println!("Week of {}, {}: {}", monday, name, plan.join(", "));
+        }
+    }
+
+    // Writes the skill log as CSV, one row per date, person and skill.
+    pub fn write_skill_log(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "date,person,skill,hours,ranks_gained")?;
+        for (date, name, skill, hours, ranks) in self.skill_log.iter() {
+            writeln!(out, "{},{},{},{},{}", date, name, skill, hours, ranks)?;
+        }
+        Ok(())
+    }
+
+    // Whether a person met all their targets, none later than the deadline.
+    pub fn finished_by(&self, name: &str, deadline: NaiveDate) -> bool {
+        !self.unmet.iter().any(|(n, _)| *n == name)
+            && self
+                .completions
+                .iter()
+                .filter(|(_, n, _)| *n == name)
+                .all(|(date, _, _)| *date <= deadline)
+    }
+
+    // Notes any completions, for days that don't otherwise count toward the totals.
+    pub fn record_completions(&mut self, results: &[PersonDayResult]) {
+        for result in results {
+            self.golden.push(golden_line(result));
+            *self.roi_so_far.entry(result.name.clone()).or_insert(0.0) += result.roi;
+            *self.wasted_so_far.entry(result.name.clone()).or_insert(0.0) += result.wasted_time;
+            for (skill, penalty) in result.penalties.iter() {
+                *self
+                    .penalties
+                    .entry((result.name.clone(), skill.clone()))
+                    .or_insert(0.0) += penalty;
+            }
+            for (skill, hours) in result.maintenance.iter() {
+                self.maintenance
+                    .entry((result.name.clone(), skill.clone()))
+                    .or_default()
+                    .0 += hours;
+            }
+            for (skill, lost) in result.decay.iter() {
+                self.maintenance
+                    .entry((result.name.clone(), skill.clone()))
+                    .or_default()
+                    .1 += lost;
+            }
+            if let Some(status) = result.solver_status.as_ref() {
+                self.non_optimal
+                    .push((result.date, result.name.clone(), status.clone()));
+                self.warnings.push(Warning {
+                    kind: WarningKind::NotOptimal,
+                    date: Some(result.date),
+                    name: Some(result.name.clone()),
+                    message: format!("{}'s plan was {}", result.name, status),
+                });
+            }
+            self.warnings.extend(result.warnings.iter().cloned());
+            if result.disagreement > CROSS_CHECK_TOLERANCE {
+                self.warnings.push(Warning {
+                    kind: WarningKind::SolverDisagreement,
+                    date: Some(result.date),
+                    name: Some(result.name.clone()),
+                    message: format!(
+                        "MiniLP and CBC disagree on {}'s plan by {:.2}%",
+                        result.name,
+                        result.disagreement * 100.0
+                    ),
+                });
+            }
+            if self
+                .worst_disagreement
+                .as_ref()
+                .is_none_or(|(worst, _, _)| result.disagreement > *worst)
+            {
+                self.worst_disagreement =
+                    Some((result.disagreement, result.date, result.name.clone()));
+            }
+            self.combos_used
+                .entry(result.name.clone())
+                .or_default()
+                .extend(result.combo_hours.keys().cloned());
+            let monday = result.date
+                - chrono::Duration::days(result.date.weekday().num_days_from_monday().into());
+            let week = self
+                .weekly_plan
+                .entry((result.name.clone(), monday))
+                .or_default();
+            for (combo, hours) in result.combo_hours.iter() {
+                *week.entry(combo.clone()).or_insert(0.0) += hours;
+            }
+            for (skill, hours) in result.hours.iter() {
+                let gained = self
+                    .skill_log
+                    .iter()
+                    .rev()
+                    .find(|(_, name, s, _, _)| *name == result.name && s == skill)
+                    .map_or(0.0, |(_, _, _, _, ranks)| *ranks);
+                self.skill_log.push((
+                    result.date,
+                    result.name.clone(),
+                    skill.clone(),
+                    *hours,
+                    gained + result.ranks[skill],
+                ));
+            }
+            for skill in result.abandoned.iter() {
+                self.unmet.push((result.name.clone(), skill.clone()));
+            }
+        }
+        for result in results.iter().filter(|r| !r.completed.is_empty()) {
+            for (skill, _) in result.completed.iter() {
+                self.completions
+                    .push((result.date, result.name.clone(), skill.clone()));
+            }
+            self.first_completion.get_or_insert(result.date);
+            self.last_completion = Some(result.date);
+        }
+    }
+
+    // Days between the first and last target completion.
+    pub fn completion_spread(&self) -> Option<i64> {
+        Some((self.last_completion? - self.first_completion?).num_days())
+    }
+
+    pub fn add_day(&mut self, date: NaiveDate, results: &[PersonDayResult]) {
+        self.record_completions(results);
+        let day_roi: f32 = results.iter().map(|r| r.roi).sum();
+        self.roi += day_roi;
+        self.wasted_time += results.iter().map(|r| r.wasted_time).sum::<f32>();
+        self.days += 1;
+        if self.best_day.is_none_or(|(roi, _)| day_roi > roi) {
+            self.best_day = Some((day_roi, date));
+        }
+        if self.worst_day.is_none_or(|(roi, _)| day_roi < roi) {
+            self.worst_day = Some((day_roi, date));
+        }
+        for result in results {
+            for (skill, hours) in result.hours.iter() {
+                let sum = self
+                    .training
+                    .entry((result.name.clone(), skill.clone()))
+                    .or_insert((0.0, 0.0));
+                sum.0 += hours;
+                sum.1 += result.ranks[skill];
+            }
+            for (seg, (used, available)) in result.segment_usage.iter() {
+                let sum = self
+                    .segment_usage
+                    .entry((result.name.clone(), seg.clone()))
+                    .or_insert((0.0, 0.0));
+                sum.0 += used;
+                sum.1 += available;
+            }
+        }
+    }
+
+    // Warnings grouped by kind, with a count for each.
+    fn print_warnings(&self) {
+        let mut by_kind: BTreeMap<WarningKind, Vec<&Warning>> = BTreeMap::new();
+        for warning in self.warnings.iter() {
+            by_kind.entry(warning.kind).or_default().push(warning);
+        }
+        println!("Warnings:");
+        for (kind, warnings) in by_kind {
+            println!("  {:?} ({}):", kind, warnings.len());
+            for warning in warnings {
+                match warning.date {
+                    Some(date) => println!("    {}: {}", date, warning.message),
+                    None => println!("    {}", warning.message),
+                }
+            }
+        }
+    }
+
+    pub fn print(&self, options: &Options) {
+        let precision = options.precision;
+        println!(
+            "Total ROI: {:.*}, ROI/day: {:.*}",
+            precision,
+            self.roi,
+            precision,
+            self.roi / self.days as f32
+        );
+        println!(
+            "Total Wasted Time: {:.*} hours, Wasted Time/day: {:.*} hours",
+            precision,
+            self.wasted_time,
+            precision,
+            self.wasted_time / self.days as f32
+        );
+        if let (Some((best_roi, best_date)), Some((worst_roi, worst_date))) =
+            (self.best_day, self.worst_day)
+        {
+            println!(
+                "Best day: {} with ROI {:.*}, worst day: {} with ROI {:.*}",
+                best_date, precision, best_roi, worst_date, precision, worst_roi
+            );
+        }
+        if let Some(spread) = self.completion_spread() {
+            println!("Completion spread: {} days", spread);
+        }
+        if !self.penalties.is_empty() {
+            println!("Neglect penalties:");
+            for ((name, skill), penalty) in self.penalties.iter() {
+                println!("  {} {}: {:.*}", name, skill, precision, penalty);
+            }
+            println!(
+                "Total ROI after penalties: {:.*}",
+                precision,
+                self.roi - self.penalties.values().sum::<f32>()
+            );
+        }
+        if !self.maintenance.is_empty() {
+            println!("Maintenance:");
+            for ((name, skill), (hours, lost)) in self.maintenance.iter() {
+                println!(
+                    "  {} {}: {:.*} hours of upkeep, {:.*} effective hours lost",
+                    name, skill, precision, hours, precision, lost
+                );
+            }
+        }
+        if options.cross_check {
+            if let Some((worst, date, name)) = self.worst_disagreement.as_ref() {
+                println!(
+                    "Worst solver disagreement: {:.2}% on {} for {}",
+                    worst * 100.0,
+                    date,
+                    name
+                );
+            }
+        }
+        if !self.warnings.is_empty() && !options.no_warnings {
+            self.print_warnings();
+        }
+        if !self.unmet.is_empty() {
+            println!("Unmet targets:");
+            for (name, skill) in self.unmet.iter() {
+                println!("  {} {}", name, skill);
+            }
+        }
+        println!("Training by skill:");
+        for ((name, skill), (hours, ranks)) in self.training.iter() {
+            println!(
+                "  {} {}: {:.*} hours (+{:.*} ranks)",
+                name, skill, precision, hours, precision, ranks
+            );
+        }
+        for (name, unused) in self.unused_overlaps.iter().filter(|(_, u)| !u.is_empty()) {
+            println!("{} never used overlaps:", name);
+            for combo in unused {
+                println!("  {}", combo.join(" + "));
+            }
+        }
+        println!("Segment utilization:");
+        for ((name, seg), (used, available)) in self.segment_usage.iter() {
+            let utilization = if *available > 0.0 {
+                100.0 * used / available
+            } else {
+                0.0
+            };
+            println!(
+                "  {} {}: {:.*} / {:.*} hours ({:.*}%)",
+                name, seg, precision, used, precision, available, precision, utilization
+            );
+        }
+    }
+}
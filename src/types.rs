@@ -1,13 +1,22 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-pub type Name = &'static str;
-pub type Skill = &'static str;
-pub type Segment = &'static str;
+// Names can come from anywhere, e.g. be generated at runtime, so they're owned.
+pub type Name = String;
+pub type Skill = String;
+pub type Segment = String;
 
 // This is required to give deterministic results.
-const DEFAULT_PRIORITY_ORDER: &[Skill] = &["Integrity", "Dreamwalking", "Illusion", "Lore"];
+const DEFAULT_PRIORITY_ORDER: &[&str] = &["Integrity", "Dreamwalking", "Illusion", "Lore"];
 // The low offset value ensures overlap bonuses aren't ignored.
-const DEFAULT_PRIORITY_OFFSET: f32 = 0.000001;
+// Per hour of segment time, a combo is worth its bonus times the average
+// preference of its skills, while training a skill alone is worth just that
+// skill's preference. So a combo is never used if its bonus is below the
+// ratio of its highest preference to its average preference.
+// --validate-only reports overlaps that fall on the wrong side of this.
+pub const DEFAULT_PRIORITY_OFFSET: f32 = 0.000001;
+// Low enough that a deprioritized skill only gets time nothing else wants,
+// but still positive so it eventually completes.
+pub const DEPRIORITIZED_PREFERENCE: f32 = 0.001;
 
 // A character is, really, just the sum of their tasks.
 // Sometimes we want to replace their components, which is done implicitly
@@ -15,13 +24,30 @@ const DEFAULT_PRIORITY_OFFSET: f32 = 0.000001;
 //
 // The simulator runs whenever At is used, and will run to completion once the
 // task list is exhausted.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum Task {
     At {
         date: chrono::NaiveDate,
     },
+    // Saves the simulation state, so that a later Rollback can return to it
+    // and try something else from there.
+    Checkpoint,
+    // Returns to the most recent Checkpoint, discarding it.
+    Rollback,
+    // Like At, but a number of days after the current date.
+    Advance {
+        days: u32,
+    },
+    // Adds a person. Skills come from the template, if any, with the
+    // explicit ones taking precedence.
     Baseline {
+        name: Name,
+        template: Option<Name>,
+        skills: BTreeMap<Skill, f32>,
+    },
+    // Defines starting skills that baselines can share.
+    Template {
         name: Name,
         skills: BTreeMap<Skill, f32>,
     },
@@ -33,9 +59,13 @@ pub enum Task {
         name: Name,
         limit: BTreeMap<Skill, f32>,
     },
+    // Restricts which skills can be trained in some segments. Combos in a
+    // restricted segment may also get a focus multiplier, for the lack of
+    // distractions.
     ScheduleLimit {
         name: Name,
         limit: BTreeMap<Segment, Vec<Skill>>,
+        focus: BTreeMap<Segment, f32>,
     },
     Overlap {
         name: Name,
@@ -45,9 +75,299 @@ pub enum Task {
         name: Name,
         target: BTreeMap<Skill, f32>,
     },
+    // Sets a segment that grows from base hours now to max hours on the
+    // deadline, for cramming, then drops back to base.
+    CramSchedule {
+        name: Name,
+        segment: Segment,
+        base: f32,
+        deadline: chrono::NaiveDate,
+        max: f32,
+    },
+    // Targets a number of effective hours for one skill, rather than a rank,
+    // replacing any earlier target for it.
+    HoursTarget {
+        name: Name,
+        skill: Skill,
+        hours: f32,
+    },
+    // Adds a pairwise overlap with the same bonus for every pair of skills.
+    Pair {
+        name: Name,
+        skills: Vec<Skill>,
+        bonus: f32,
+    },
+    // Temporarily multiplies one skill's preference, up to and including `until`.
+    Focus {
+        name: Name,
+        skill: Skill,
+        multiplier: f32,
+        until: chrono::NaiveDate,
+    },
+    // Applies each of the tasks to every one of the named people, in order.
+    // The name given in the inner tasks is ignored; use "" by convention.
+    Batch {
+        names: Vec<Name>,
+        tasks: Vec<Task>,
+    },
+    // Reports how much finishing one target first delays the other,
+    // compared to training them interleaved.
+    Tradeoff {
+        name: Name,
+        first: Skill,
+        second: Skill,
+    },
+    // Training the primary skill also credits the others, at the given
+    // fraction of the effective hours.
+    Compound {
+        name: Name,
+        primary: Skill,
+        also: BTreeMap<Skill, f32>,
+    },
+    // Fixes the hours spent on a combo in a segment, leaving the solver to
+    // optimize everything else around it.
+    Pin {
+        name: Name,
+        segment: Segment,
+        combo: Vec<Skill>,
+        hours: f32,
+    },
+    // Limits how many targeted skills can be trained on any one day.
+    MaxConcurrent {
+        name: Name,
+        n: usize,
+    },
+    // Only train this skill once everything else is satisfied.
+    Deprioritize {
+        name: Name,
+        skill: Skill,
+    },
+    // Multiplies one skill's preference by a weight, leaving the rest alone.
+    Prefer {
+        name: Name,
+        skill: Skill,
+        weight: f32,
+    },
+    // Records a metric for this person every day, for the metrics CSV.
+    Observe {
+        name: Name,
+        metric: Metric,
+    },
+    // How effective an hour of training is in each segment; e.g. a segment
+    // with a teacher might be worth 1.5 hours of self-study. Unlisted
+    // segments are 1.0.
+    Quality {
+        name: Name,
+        quality: BTreeMap<Segment, f32>,
+    },
+    // Creates `count` people named "<prefix> 1", "<prefix> 2", ..., each with
+    // random ranks in the given ranges on top of the template, if any, then
+    // applies the tasks to all of them as a batch. The same seed always
+    // generates the same cohort.
+    GenerateCohort {
+        prefix: Name,
+        count: usize,
+        template: Option<Name>,
+        skill_ranges: BTreeMap<Skill, (f32, f32)>,
+        seed: u64,
+        tasks: Vec<Task>,
+    },
+    // Renames a skill for every person, e.g. after a change in terminology.
+    RenameSkill {
+        from: Skill,
+        to: Skill,
+    },
+    // A target that only starts on a given date, costed from the skill's
+    // rank at that point rather than now.
+    Requirement {
+        name: Name,
+        skill: Skill,
+        rank: f32,
+        not_before: chrono::NaiveDate,
+    },
+    // A series of target ranks for one skill, each with a deadline. They are
+    // trained for in rank order, and each is reported against its deadline.
+    // A later Target task replaces them.
+    Checkpoints {
+        name: Name,
+        skill: Skill,
+        checkpoints: Vec<(f32, chrono::NaiveDate)>,
+    },
+    // Charges a penalty for every day past the grace period that a skill
+    // goes without training. This is only accounted for afterwards; the
+    // solver doesn't try to avoid it.
+    Neglect {
+        name: Name,
+        skill: Skill,
+        grace_days: u32,
+        penalty: f32,
+    },
+    // Sets upkeep a skill needs each week, which the solver reserves time for
+    // ahead of any training. Whatever falls short over the last week is lost
+    // again from the skill, as effective hours.
+    Maintenance {
+        name: Name,
+        skill: Skill,
+        hours_per_week: f32,
+    },
+    // Exchanges the targets of two skills, recosted from each skill's current
+    // rank. With carry_progress, the hours already put towards each target
+    // move with it, instead of being lost.
+    Swap {
+        name: Name,
+        a: Skill,
+        b: Skill,
+        carry_progress: bool,
+    },
+    // Multiplies the bonus of one combo, or of every combo if none is given,
+    // until the given date.
+    Boost {
+        name: Name,
+        combo: Option<Vec<Skill>>,
+        multiplier: f32,
+        until: chrono::NaiveDate,
+    },
+    // Adds a linear constraint on the daily time spent per skill, e.g.
+    // Illusion - 0.5 * Dreamwalking >= 0.
+    Constraint {
+        name: Name,
+        terms: Vec<(Skill, f32)>,
+        op: Comparison,
+        rhs: f32,
+    },
+    // Scales the whole schedule by a multiplier per week, counting from when
+    // the task runs. Weeks past the end of the list are unscaled.
+    TimeBudgetByWeek {
+        name: Name,
+        weeks: Vec<f32>,
+    },
+    // Banks time left unused until accrue_until, and makes it available on
+    // top of the schedule from then until spend_until.
+    Sabbatical {
+        name: Name,
+        accrue_until: chrono::NaiveDate,
+        spend_until: chrono::NaiveDate,
+    },
+    // Caps the total effective hours this person can ever train.
+    LifetimeBudget {
+        name: Name,
+        hours: f32,
+    },
+    // Prints the current ranks, remaining targets and ROI so far for these
+    // people, or everyone.
+    Report {
+        names: Option<Vec<Name>>,
+    },
+    // Gives up ranks in one skill, no further than rank 0, and puts a ratio
+    // of the effective hours they cost into another skill, up to its target.
+    Respec {
+        name: Name,
+        from: Skill,
+        to: Skill,
+        ranks: f32,
+        ratio: f32,
+    },
+    // Prints this person's whole LP, solution and constraint slack on a date.
+    DebugProblem {
+        name: Name,
+        date: chrono::NaiveDate,
+    },
+    // Changes how much is logged from here on, e.g. to debug a single week.
+    Log {
+        level: log::LevelFilter,
+    },
+    // Applies every later Schedule and Overlap task for the leader to the
+    // follower as well, replacing whoever they followed before.
+    Mirror {
+        follower: Name,
+        leader: Name,
+    },
+    // A target met once the participants' ranks in a skill add up to the
+    // combined rank. Each trains toward the rank that would meet it alone,
+    // and stops once it's met together.
+    GroupTarget {
+        names: Vec<Name>,
+        skill: Skill,
+        combined_rank: f32,
+    },
+    // Makes some skills advance in bands other than whole ranks, e.g. 0.5
+    // for half-steps. Each band costs its share of the rank it starts in.
+    // Targets set earlier keep their cost.
+    RankStep {
+        name: Name,
+        step: BTreeMap<Skill, f32>,
+    },
+    // Marks recurring days on which this person can't train at all,
+    // replacing any earlier pattern.
+    Availability {
+        name: Name,
+        unavailable: Vec<DatePattern>,
+    },
+    // Adds time to the schedule on recurring days, e.g. a weekly lesson,
+    // replacing any earlier calendar.
+    Calendar {
+        name: Name,
+        rules: Vec<RecurrenceRule>,
+    },
 }
 
-#[derive(Debug)]
+impl Task {
+    // Returns the same task, aimed at a different person.
+    pub fn with_name(mut self, new_name: Name) -> Self {
+        match &mut self {
+            Task::At { .. }
+            | Task::Checkpoint
+            | Task::Report { .. }
+            | Task::Mirror { .. }
+            | Task::GroupTarget { .. }
+            | Task::Log { .. }
+            | Task::Rollback
+            | Task::Advance { .. }
+            | Task::Batch { .. }
+            | Task::GenerateCohort { .. }
+            | Task::Template { .. }
+            | Task::RenameSkill { .. } => {
+                panic!("Task can't be applied to a person: {:?}", self)
+            }
+            Task::Baseline { name, .. }
+            | Task::Schedule { name, .. }
+            | Task::SafetyLimit { name, .. }
+            | Task::ScheduleLimit { name, .. }
+            | Task::Overlap { name, .. }
+            | Task::Target { name, .. }
+            | Task::HoursTarget { name, .. }
+            | Task::CramSchedule { name, .. }
+            | Task::Pair { name, .. }
+            | Task::Focus { name, .. }
+            | Task::Tradeoff { name, .. }
+            | Task::Compound { name, .. }
+            | Task::Pin { name, .. }
+            | Task::MaxConcurrent { name, .. }
+            | Task::Deprioritize { name, .. }
+            | Task::Prefer { name, .. }
+            | Task::Observe { name, .. }
+            | Task::Quality { name, .. }
+            | Task::Requirement { name, .. }
+            | Task::Checkpoints { name, .. }
+            | Task::Neglect { name, .. }
+            | Task::Maintenance { name, .. }
+            | Task::Swap { name, .. }
+            | Task::Constraint { name, .. }
+            | Task::Boost { name, .. }
+            | Task::Sabbatical { name, .. }
+            | Task::TimeBudgetByWeek { name, .. }
+            | Task::LifetimeBudget { name, .. }
+            | Task::RankStep { name, .. }
+            | Task::DebugProblem { name, .. }
+            | Task::Respec { name, .. }
+            | Task::Availability { name, .. }
+            | Task::Calendar { name, .. } => *name = new_name,
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Person {
     pub name: Name,
     // This person's skills, training or not.
@@ -59,6 +379,8 @@ pub struct Person {
     // Limits to which skills can be trained in which segments.
     // Some segments have no limit, and are not listed here.
     pub schedule_limit: BTreeMap<Segment, Vec<Skill>>,
+    // Bonus multipliers for training in some of those limited segments.
+    pub limit_focus: BTreeMap<Segment, f32>,
     // Overlap bonuses for training multiple skills at once.
     // This *includes* the trivial case of training a single skill.
     pub overlap: Vec<Overlap>,
@@ -66,20 +388,65 @@ pub struct Person {
     pub target: BTreeMap<Skill, Target>,
     // Skill prefereces for training; defines which skills are trained first,
     // and by how much they're preferred. 1.0 is neutral; lower is less.
+    // Negative values (with --allow-negative-preference) actively avoid a
+    // skill; it is then only trained with time that nothing else can use.
     // A skill's presence in this map does not imply the person is even capable
     // of training it.
     pub preference: BTreeMap<Skill, f32>,
+    // Temporary preference multipliers, applied on top of `preference`.
+    pub focus: BTreeMap<Skill, Focus>,
+    // Skills whose training spills over into others, by primary skill.
+    pub compound: BTreeMap<Skill, BTreeMap<Skill, f32>>,
+    // Fixed allocations of hours to combos, by segment and combo.
+    pub pin: BTreeMap<(Segment, Vec<Skill>), f32>,
+    // The most targeted skills that can be trained in a single day, if limited.
+    pub max_concurrent: Option<usize>,
+    // Metrics recorded for this person every day.
+    pub observe: BTreeSet<Metric>,
+    // Training quality multipliers, by segment.
+    pub quality: BTreeMap<Segment, f32>,
+    // Effective training hours left over the rest of the run, if limited.
+    pub lifetime_budget: Option<f32>,
+    // Schedule multipliers by week, and the date the first week starts.
+    pub weekly_time: Option<(chrono::NaiveDate, Vec<f32>)>,
+    // Target ranks that don't start until a given date, by skill.
+    pub pending: BTreeMap<Skill, (f32, chrono::NaiveDate)>,
+    // Extra linear constraints on daily time spent per skill.
+    pub constraints: Vec<LinearConstraint>,
+    // Temporary bonus multipliers for combos.
+    pub boosts: Vec<Boost>,
+    // Unused time saved up for a later burst of training, if any.
+    pub sabbatical: Option<Sabbatical>,
+    // Target ranks with deadlines, by skill. The first one of each is the
+    // skill's current target.
+    pub checkpoints: BTreeMap<Skill, VecDeque<(f32, chrono::NaiveDate)>>,
+    // Skills that are penalized for going untrained.
+    pub neglect: BTreeMap<Skill, Neglect>,
+    // Upkeep needed to keep skills from decaying.
+    pub maintenance: BTreeMap<Skill, Maintenance>,
+    // Days on which this person can't train at all.
+    pub unavailable: Vec<DatePattern>,
+    // Time added to the schedule on recurring days.
+    pub calendar: Vec<RecurrenceRule>,
+    // Segments that grow towards a deadline, by segment.
+    pub cram: BTreeMap<Segment, Cram>,
+    // The size of each rank band, for skills that don't advance by whole ranks.
+    pub rank_step: BTreeMap<Skill, f32>,
+    // The highest rank held by skills that have since dropped below it.
+    pub peak: BTreeMap<Skill, f32>,
+    // Dates to dump this person's LP on, for debugging.
+    pub debug_problem: BTreeSet<chrono::NaiveDate>,
 }
 
 impl Person {
-    pub fn new(name: Name, skills: BTreeMap<Skill, f32>) -> Self {
+    pub fn new(name: Name, skills: BTreeMap<Skill, f32>, priority_offset: f32) -> Self {
         // Generate a default preference map.
         // We start at 1.0, then just add the offset per-skill.
         let preference = DEFAULT_PRIORITY_ORDER
             .iter()
             .rev()
             .enumerate()
-            .map(|(i, skill)| (*skill, 1.0 + i as f32 * DEFAULT_PRIORITY_OFFSET))
+            .map(|(i, skill)| (skill.to_string(), 1.0 + i as f32 * priority_offset))
             .collect();
 
         Self {
@@ -88,21 +455,389 @@ impl Person {
             schedule: BTreeMap::new(),
             safety_limit: BTreeMap::new(),
             schedule_limit: BTreeMap::new(),
+            limit_focus: BTreeMap::new(),
             overlap: vec![],
             target: BTreeMap::new(),
             preference,
+            focus: BTreeMap::new(),
+            compound: BTreeMap::new(),
+            pin: BTreeMap::new(),
+            max_concurrent: None,
+            observe: BTreeSet::new(),
+            quality: BTreeMap::new(),
+            lifetime_budget: None,
+            weekly_time: None,
+            pending: BTreeMap::new(),
+            constraints: vec![],
+            boosts: vec![],
+            sabbatical: None,
+            checkpoints: BTreeMap::new(),
+            neglect: BTreeMap::new(),
+            maintenance: BTreeMap::new(),
+            unavailable: vec![],
+            calendar: vec![],
+            cram: BTreeMap::new(),
+            rank_step: BTreeMap::new(),
+            peak: BTreeMap::new(),
+            debug_problem: BTreeSet::new(),
+        }
+    }
+
+    // The training quality multiplier for a segment.
+    pub fn quality_in(&self, segment: &str) -> f32 {
+        self.quality.get(segment).copied().unwrap_or(1.0)
+    }
+
+    // Sets a skill's rank. A rank that drops is remembered, so that getting
+    // it back costs less.
+    pub fn set_rank(&mut self, skill: &str, rank: f32) {
+        if let Some(old) = self.skills.insert(skill.to_string(), rank) {
+            if rank < old {
+                let peak = self.peak.entry(skill.to_string()).or_insert(old);
+                *peak = peak.max(old);
+            }
+        }
+    }
+
+    // The size of a skill's rank bands.
+    pub fn rank_step_of(&self, skill: &str) -> f32 {
+        self.rank_step.get(skill).copied().unwrap_or(1.0)
+    }
+
+    // The focus multiplier for training in a limited segment.
+    pub fn limit_focus_in(&self, segment: &str) -> f32 {
+        self.limit_focus.get(segment).copied().unwrap_or(1.0)
+    }
+
+    // How much of the schedule is available on a given day.
+    pub fn time_scale_on(&self, now: chrono::NaiveDate) -> f32 {
+        if !self.available_on(now) {
+            return 0.0;
+        }
+        let Some((start, weeks)) = self.weekly_time.as_ref() else {
+            return 1.0;
+        };
+        let week = (now - *start).num_days() / 7;
+        usize::try_from(week)
+            .ok()
+            .and_then(|week| weeks.get(week))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    // The schedule for a given day, with any calendar time added in. This is
+    // before time_scale_on.
+    pub fn schedule_on(&self, now: chrono::NaiveDate) -> BTreeMap<Segment, f32> {
+        let mut schedule = self.schedule.clone();
+        for (seg, cram) in self.cram.iter() {
+            schedule.insert(seg.clone(), cram.hours_on(now));
+        }
+        for rule in self.calendar.iter().filter(|rule| rule.matches(now)) {
+            *schedule.entry(rule.segment.clone()).or_insert(0.0) += rule.hours;
+        }
+        schedule
+    }
+
+    // Whether this person can train at all on a given day.
+    pub fn available_on(&self, now: chrono::NaiveDate) -> bool {
+        !self.unavailable.iter().any(|pattern| pattern.matches(now))
+    }
+
+    // The banked sabbatical time that can be spent on a given day, if any.
+    pub fn banked_on(&self, now: chrono::NaiveDate) -> Option<f32> {
+        let sabbatical = self.sabbatical.as_ref()?;
+        let spending = sabbatical.accrue_until < now
+            && now <= sabbatical.spend_until
+            && self.available_on(now);
+        (spending && sabbatical.banked > 0.0).then_some(sabbatical.banked)
+    }
+
+    // The combined multiplier of all boosts to a combo on a given day.
+    pub fn boost_on(&self, combo: &[Skill], now: chrono::NaiveDate) -> f32 {
+        self.boosts
+            .iter()
+            .filter(|boost| now <= boost.until)
+            .filter(|boost| boost.combo.as_deref().is_none_or(|c| c == combo))
+            .map(|boost| boost.multiplier)
+            .product()
+    }
+
+    // The effective preference for a skill on a given day.
+    // Skills without an explicit preference are neutral.
+    pub fn preference_on(&self, skill: &str, now: chrono::NaiveDate) -> f32 {
+        let base = self.preference.get(skill).copied().unwrap_or(1.0);
+        match self.focus.get(skill) {
+            Some(focus) if now <= focus.until => base * focus.multiplier,
+            _ => base,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Overlap {
+    // Entries may be category wildcards such as "@ATTRIBUTES", which are
+    // expanded into concrete combos when the overlap task runs.
     pub combo: Vec<Skill>,
     pub bonus: f32,
+    // Bonuses that replace the base bonus in particular segments.
+    pub segment_bonus: BTreeMap<Segment, f32>,
+    // Ranks some skills must have reached before there's any bonus at all.
+    pub min_rank: BTreeMap<Skill, f32>,
 }
 
-#[derive(Debug)]
+impl Overlap {
+    // The bonus for training this combo in a given segment.
+    pub fn bonus_in(&self, segment: &str) -> f32 {
+        self.segment_bonus
+            .get(segment)
+            .copied()
+            .unwrap_or(self.bonus)
+    }
+
+    // The bonus in a segment for someone with these ranks. Until the
+    // minimum ranks are met, the combo is no better than training alone.
+    pub fn bonus_for(&self, segment: &str, skills: &BTreeMap<Skill, f32>) -> f32 {
+        let unlocked = self
+            .min_rank
+            .iter()
+            .all(|(skill, rank)| skills.get(skill).is_some_and(|r| r >= rank));
+        if unlocked {
+            self.bonus_in(segment)
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub enum Comparison {
+    AtMost,
+    AtLeast,
+    Equal,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearConstraint {
+    pub terms: Vec<(Skill, f32)>,
+    pub op: Comparison,
+    pub rhs: f32,
+}
+
+#[derive(Debug, Clone)]
 pub struct Target {
     pub target_rank: f32,
     pub hours_needed: f32,
 }
+
+#[derive(Debug, Clone)]
+pub struct Boost {
+    // The combo boosted, or None for all of them.
+    pub combo: Option<Vec<Skill>>,
+    pub multiplier: f32,
+    pub until: chrono::NaiveDate,
+}
+
+// A recurring set of dates.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub enum DatePattern {
+    // Every such weekday.
+    Weekday(chrono::Weekday),
+    // The same day of every month.
+    DayOfMonth(u32),
+    // The nth such weekday of every month, counting from 1.
+    NthWeekday(u32, chrono::Weekday),
+    // Just this one date.
+    Date(chrono::NaiveDate),
+}
+
+impl DatePattern {
+    pub fn matches(&self, date: chrono::NaiveDate) -> bool {
+        use chrono::Datelike;
+        match self {
+            DatePattern::Weekday(weekday) => date.weekday() == *weekday,
+            DatePattern::DayOfMonth(day) => date.day() == *day,
+            DatePattern::NthWeekday(n, weekday) => {
+                date.weekday() == *weekday && (date.day() - 1) / 7 + 1 == *n
+            }
+            DatePattern::Date(day) => date == *day,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cram {
+    pub base: f32,
+    pub max: f32,
+    // The day it's at base, and the day it reaches max.
+    pub start: chrono::NaiveDate,
+    pub deadline: chrono::NaiveDate,
+}
+
+impl Cram {
+    // The segment's hours on a given day: rising linearly up to the deadline.
+    pub fn hours_on(&self, now: chrono::NaiveDate) -> f32 {
+        if now < self.start || now > self.deadline {
+            return self.base;
+        }
+        let span = (self.deadline - self.start).num_days().max(1) as f32;
+        let progress = (now - self.start).num_days() as f32 / span;
+        self.base + (self.max - self.base) * progress
+    }
+}
+
+// Time in a segment on every day matching any of the patterns.
+#[derive(Debug, Clone)]
+pub struct RecurrenceRule {
+    pub segment: Segment,
+    pub hours: f32,
+    pub on: Vec<DatePattern>,
+}
+
+impl RecurrenceRule {
+    pub fn matches(&self, date: chrono::NaiveDate) -> bool {
+        self.on.iter().any(|pattern| pattern.matches(date))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sabbatical {
+    pub accrue_until: chrono::NaiveDate,
+    pub spend_until: chrono::NaiveDate,
+    // Hours saved up and not yet spent.
+    pub banked: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Neglect {
+    pub grace_days: u32,
+    pub penalty: f32,
+    // Consecutive days without training so far.
+    pub idle_days: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Maintenance {
+    pub hours_per_week: f32,
+    // Upkeep credited on each of the last six days, oldest first. Upkeep
+    // missed and lost to decay is credited too, so it's only lost once.
+    pub done: VecDeque<f32>,
+}
+
+impl Maintenance {
+    // The upkeep still needed today for the week ending today to have had
+    // enough. Days before upkeep was set up count as having had their share.
+    pub fn due_today(&self) -> f32 {
+        let share = self.hours_per_week / 7.0;
+        let missing = 6 - self.done.len().min(6);
+        let recent = self.done.iter().sum::<f32>() + missing as f32 * share;
+        (self.hours_per_week - recent).max(0.0)
+    }
+
+    // Records a day's upkeep, and returns the effective hours lost for the
+    // week falling short.
+    pub fn record(&mut self, hours: f32) -> f32 {
+        let shortfall = (self.due_today() - hours).max(0.0);
+        self.done.push_back(hours + shortfall);
+        while self.done.len() > 6 {
+            self.done.pop_front();
+        }
+        shortfall
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Focus {
+    pub multiplier: f32,
+    pub until: chrono::NaiveDate,
+}
+
+// The outcome of one simulated day for one person.
+#[derive(Debug, Clone)]
+pub struct PersonDayResult {
+    pub name: Name,
+    pub date: chrono::NaiveDate,
+    pub roi: f32,
+    pub wasted_time: f32,
+    // Effective hours trained, per skill.
+    pub hours: BTreeMap<Skill, f32>,
+    // The same training, converted to fractional ranks gained.
+    pub ranks: BTreeMap<Skill, f32>,
+    // Hours used and available, per segment.
+    pub segment_usage: BTreeMap<Segment, (f32, f32)>,
+    // Skills that reached their target rank today, with the rank reached.
+    pub completed: Vec<(Skill, f32)>,
+    // Targets given up on today, because they can no longer be met.
+    pub abandoned: Vec<Skill>,
+    // Schedule hours spent on each combo that got any time today.
+    pub combo_hours: BTreeMap<Vec<Skill>, f32>,
+    // The solver's status, if any of today's plans weren't optimal.
+    pub solver_status: Option<String>,
+    // What had to give to find a feasible plan, with --auto-relax.
+    pub relaxed: Vec<String>,
+    // Checkpoints reached today, with their rank and deadline.
+    pub checkpoints: Vec<(Skill, f32, chrono::NaiveDate)>,
+    // Neglect penalties charged today, by skill.
+    pub penalties: Vec<(Skill, f32)>,
+    // Anything odd noticed while planning the day.
+    pub warnings: Vec<Warning>,
+    // How far MiniLP and CBC disagreed on today's plans, with --cross-check.
+    pub disagreement: f32,
+    // Hours of upkeep done today, by skill.
+    pub maintenance: BTreeMap<Skill, f32>,
+    // Effective hours lost today to missed upkeep, by skill.
+    pub decay: BTreeMap<Skill, f32>,
+}
+
+// Something worth pointing out about a run, short of a problem that stops it.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub date: Option<chrono::NaiveDate>,
+    pub name: Option<Name>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WarningKind {
+    // A person's name differs only by case from another's.
+    SimilarName,
+    // Overlaps given more than once for the same combo.
+    DuplicateOverlap,
+    // Limits, qualities or pins in segments that aren't in the schedule.
+    UnknownSegment,
+    // Targets that would take longer than --max-days.
+    SlowTargets,
+    // Checkpoints that can't be met by their deadline.
+    UnreachableCheckpoint,
+    // Days whose ROI moved under --perturb-test.
+    UnstableRoi,
+    // Days the solver didn't solve to optimality.
+    NotOptimal,
+    // Days MiniLP and CBC disagreed on, with --cross-check.
+    SolverDisagreement,
+}
+
+// Quantities that can be recorded every day with Task::Observe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(dead_code)]
+pub enum Metric {
+    TotalRoi,
+    WastedTime,
+    AttributeRoi,
+    AbilityRoi,
+    PsionicRoi,
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::TotalRoi => "total_roi",
+            Metric::WastedTime => "wasted_time",
+            Metric::AttributeRoi => "attribute_roi",
+            Metric::AbilityRoi => "ability_roi",
+            Metric::PsionicRoi => "psionic_roi",
+        }
+    }
+}
@@ -0,0 +1,347 @@
+// Warm-started solves with MiniLP, for --warm-start.
+//
+// lp-modeler builds a fresh MiniLP problem for every solve, and keeps it to
+// itself. MiniLP can only pick up from an old solution by fixing variables
+// or adding constraints to it, so here each constraint's right-hand side is
+// a variable of its own, fixed to its value. When a problem has the same
+// objective and left-hand sides as the last one solved under its key, as
+// consecutive days' problems for one person mostly do, only the right-hand
+// sides that changed are fixed again, starting from the last basis.
+
+use lp_modeler::dsl::{Constraint, LpExpression, LpObjective, LpProblem};
+use lp_modeler::format::lp_format::LpFileFormat;
+use lp_modeler::solvers::{Solution, Status};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::iter::Peekable;
+use std::str::Chars;
+
+// How far right-hand sides may range before they're fixed. MiniLP needs them
+// bounded, or the first solve could be unbounded; anything larger than any
+// real right-hand side will do.
+const RHS_LIMIT: f64 = 1e6;
+// What loosening a constraint is worth before its right-hand side is fixed,
+// so that each starts out at one of its bounds. MiniLP can fix a variable
+// that isn't in the basis more reliably than one that is. Once fixed, this
+// only adds a constant to the objective.
+const LOOSEN_WEIGHT: f64 = 1e-3;
+
+// A problem with everything but its right-hand sides: the objective, then
+// each constraint's left-hand side and comparison, as lp-modeler writes them.
+type Shape = Vec<String>;
+
+struct Warm {
+    shape: Shape,
+    // The variables right-hand sides are fixed in, with the constraint each
+    // belongs to and its value.
+    rhs: Vec<(minilp::Variable, usize, f64)>,
+    // The problem's own variables.
+    variables: Vec<(String, minilp::Variable)>,
+    solution: minilp::Solution,
+}
+
+thread_local! {
+    // The last problem solved under each key.
+    static LAST: RefCell<HashMap<String, Warm>> = RefCell::new(HashMap::new());
+}
+
+// Solves a problem, starting from the last one solved under the same key if
+// it has the same shape. Infeasible and unbounded problems aren't kept.
+pub fn solve(key: &str, problem: &LpProblem) -> Solution<'static> {
+    let objective = problem
+        .obj_expr_arena
+        .as_ref()
+        .expect("Missing objective")
+        .to_lp_file_format();
+    let mut shape = vec![objective];
+    let mut rhs = vec![];
+    for constraint in problem.constraints.iter() {
+        let op = match constraint.1 {
+            Constraint::LessOrEqual => "<=",
+            Constraint::GreaterOrEqual => ">=",
+            Constraint::Equal => "=",
+        };
+        shape.push(format!("{} {}", constraint.0.to_lp_file_format(), op));
+        let (terms, constant) = linear(&constraint.2);
+        assert!(terms.is_empty(), "Right-hand side with variables");
+        rhs.push(constant as f64);
+    }
+    LAST.with(|last| {
+        let mut last = last.borrow_mut();
+        let warm = match last.remove(key) {
+            Some(warm) if warm.shape == shape => {
+                refix(warm, &rhs).or_else(|_| cold(problem, shape, &rhs))
+            }
+            _ => cold(problem, shape, &rhs),
+        };
+        let warm = match warm {
+            Ok(warm) => warm,
+            Err(minilp::Error::Infeasible) => {
+                return Solution::new(Status::Infeasible, HashMap::new())
+            }
+            Err(minilp::Error::Unbounded) => {
+                return Solution::new(Status::Unbounded, HashMap::new())
+            }
+        };
+        let results = warm
+            .variables
+            .iter()
+            .map(|(name, var)| (name.clone(), warm.solution[*var] as f32))
+            .collect();
+        last.insert(key.to_string(), warm);
+        Solution::new(Status::Optimal, results)
+    })
+}
+
+// Builds and solves a problem from scratch, with its right-hand sides free
+// at first, then fixing them one at a time. Each step only tightens the last,
+// so this fails only if the problem itself is infeasible.
+fn cold(problem: &LpProblem, shape: Shape, rhs: &[f64]) -> Result<Warm, minilp::Error> {
+    let direction = match problem.objective_type {
+        LpObjective::Maximize => minilp::OptimizationDirection::Maximize,
+        LpObjective::Minimize => minilp::OptimizationDirection::Minimize,
+    };
+    let mut lp = minilp::Problem::new(direction);
+    let mut variables: HashMap<String, minilp::Variable> = HashMap::new();
+    let (objective, _) = linear(problem.obj_expr_arena.as_ref().unwrap());
+    for (name, coefficient) in objective {
+        let var = lp.add_var(coefficient.into(), (f64::NEG_INFINITY, f64::INFINITY));
+        variables.insert(name, var);
+    }
+    // The right-hand sides, as variables. An equality is split in two, so
+    // that each side can start out loose.
+    let mut fixed = vec![];
+    let loosen = match direction {
+        minilp::OptimizationDirection::Maximize => LOOSEN_WEIGHT,
+        minilp::OptimizationDirection::Minimize => -LOOSEN_WEIGHT,
+    };
+    for (i, constraint) in problem.constraints.iter().enumerate() {
+        let mut expr = minilp::LinearExpr::empty();
+        for (name, coefficient) in linear(&constraint.0).0 {
+            let var = *variables
+                .entry(name)
+                .or_insert_with(|| lp.add_var(0.0, (f64::NEG_INFINITY, f64::INFINITY)));
+            expr.add(var, coefficient.into());
+        }
+        let ops: &[minilp::ComparisonOp] = match constraint.1 {
+            Constraint::LessOrEqual => &[minilp::ComparisonOp::Le],
+            Constraint::GreaterOrEqual => &[minilp::ComparisonOp::Ge],
+            Constraint::Equal => &[minilp::ComparisonOp::Le, minilp::ComparisonOp::Ge],
+        };
+        for op in ops {
+            let weight = match op {
+                minilp::ComparisonOp::Ge => -loosen,
+                _ => loosen,
+            };
+            let var = lp.add_var(weight, (-RHS_LIMIT, RHS_LIMIT));
+            let mut expr = expr.clone();
+            expr.add(var, -1.0);
+            lp.add_constraint(expr, *op, 0.0);
+            fixed.push((var, i, rhs[i]));
+        }
+    }
+    let mut solution = lp.solve()?;
+    for (var, _, value) in fixed.iter() {
+        solution = solution.fix_var(*var, *value)?;
+    }
+    Ok(Warm {
+        shape,
+        rhs: fixed,
+        variables: variables.into_iter().collect(),
+        solution,
+    })
+}
+
+// Fixes the right-hand sides that changed to their new values. Changing them
+// in turn can pass through an infeasible mix of old and new, so that's left
+// to a cold solve to decide.
+fn refix(mut warm: Warm, rhs: &[f64]) -> Result<Warm, minilp::Error> {
+    let mut solution = warm.solution;
+    for (var, row, old) in warm.rhs.iter_mut() {
+        if *old != rhs[*row] {
+            solution = solution.fix_var(*var, rhs[*row])?;
+            *old = rhs[*row];
+        }
+    }
+    warm.solution = solution;
+    Ok(warm)
+}
+
+// A node of an expression tree, as lp-modeler stores it.
+enum Node {
+    Var(String),
+    Lit(f32),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Empty,
+}
+
+// An expression's terms, summed by name, and its constant. This walks the
+// expression tree the way lp-modeler's own MiniLP backend does, so names can
+// hold anything, spaces included.
+fn linear(expr: &LpExpression) -> (BTreeMap<String, f32>, f32) {
+    let (root, nodes) = tree(expr);
+    let mut terms = BTreeMap::new();
+    let mut constant = 0.0;
+    let mut pending = vec![(1.0, root)];
+    while let Some((factor, i)) = pending.pop() {
+        match nodes[i] {
+            Node::Var(ref name) => *terms.entry(name.clone()).or_insert(0.0) += factor,
+            Node::Lit(value) => constant += factor * value,
+            Node::Add(lhs, rhs) => pending.extend([(factor, lhs), (factor, rhs)]),
+            Node::Sub(lhs, rhs) => pending.extend([(factor, lhs), (-factor, rhs)]),
+            Node::Mul(lhs, rhs) => match (&nodes[lhs], &nodes[rhs]) {
+                (Node::Lit(value), _) => pending.push((factor * value, rhs)),
+                (_, Node::Lit(value)) => pending.push((factor * value, lhs)),
+                _ => panic!("Non-linear expression: {:?}", expr),
+            },
+            Node::Empty => {}
+        }
+    }
+    (terms, constant)
+}
+
+// lp-modeler keeps the tree itself private, so this reads it back from the
+// expression's Debug output, which lists every node, e.g.
+// LpExpression { root: 2, arena: [ConsCont(LpContinuous { name: "a", .. }),
+// LitVal(2.0), LpCompExpr(Multiplication, 1, 0)] }
+fn tree(expr: &LpExpression) -> (usize, Vec<Node>) {
+    let text = format!("{:?}", expr);
+    read_tree(&debug(&mut text.chars().peekable()))
+        .unwrap_or_else(|| panic!("Unexpected expression: {}", text))
+}
+
+fn read_tree(expr: &Debug) -> Option<(usize, Vec<Node>)> {
+    let [root, arena] = expr.args()? else {
+        return None;
+    };
+    let nodes = arena.args()?.iter().map(read_node).collect::<Option<_>>()?;
+    Some((root.number()?, nodes))
+}
+
+fn read_node(node: &Debug) -> Option<Node> {
+    Some(match (node.name()?, node.args()?) {
+        ("ConsCont" | "ConsInt" | "ConsBin", [var]) => match var.args()?.first()? {
+            Debug::Str(name) => Node::Var(name.clone()),
+            _ => return None,
+        },
+        ("LitVal", [value]) => Node::Lit(value.number()?),
+        ("EmptyExpr", []) => Node::Empty,
+        ("LpCompExpr", [op, lhs, rhs]) => {
+            let (lhs, rhs) = (lhs.number()?, rhs.number()?);
+            match op.name()? {
+                "Addition" => Node::Add(lhs, rhs),
+                "Subtraction" => Node::Sub(lhs, rhs),
+                "Multiplication" => Node::Mul(lhs, rhs),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    })
+}
+
+// A value in Debug output: a string, or anything else by its name, with its
+// fields or arguments. Field names are dropped, and a list has no name.
+enum Debug {
+    Str(String),
+    Item(String, Vec<Debug>),
+}
+
+impl Debug {
+    fn name(&self) -> Option<&str> {
+        match self {
+            Debug::Item(name, _) => Some(name),
+            Debug::Str(_) => None,
+        }
+    }
+
+    fn args(&self) -> Option<&[Debug]> {
+        match self {
+            Debug::Item(_, args) => Some(args),
+            Debug::Str(_) => None,
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self) -> Option<T> {
+        self.name()?.parse().ok()
+    }
+}
+
+// Reads one value of Debug output.
+fn debug(chars: &mut Peekable<Chars>) -> Debug {
+    skip_spaces(chars);
+    if chars.next_if_eq(&'"').is_some() {
+        let mut text = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => text.push(unescape(chars)),
+                c => text.push(c),
+            }
+        }
+        return Debug::Str(text);
+    }
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || "_.+-".contains(*c)) {
+        name.push(c);
+    }
+    skip_spaces(chars);
+    let close = match chars.peek() {
+        Some('(') => ')',
+        Some('{') => '}',
+        Some('[') => ']',
+        _ => {
+            // Always make progress, even on something unexpected.
+            if name.is_empty() {
+                name.extend(chars.next());
+            }
+            return Debug::Item(name, vec![]);
+        }
+    };
+    chars.next();
+    let mut args = vec![];
+    loop {
+        skip_spaces(chars);
+        match chars.peek() {
+            None => break,
+            Some(c) if *c == close => {
+                chars.next();
+                break;
+            }
+            Some(',') => {
+                chars.next();
+            }
+            // What came before was a field name.
+            Some(':') => {
+                chars.next();
+                args.pop();
+            }
+            Some(_) => args.push(debug(chars)),
+        }
+    }
+    Debug::Item(name, args)
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+// The character an escape in a Debug string stands for, after the backslash.
+fn unescape(chars: &mut Peekable<Chars>) -> char {
+    match chars.next() {
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('0') => '\0',
+        Some('u') => {
+            let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+            u32::from_str_radix(&code, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+        }
+        Some(c) => c,
+        None => '\\',
+    }
+}